@@ -0,0 +1,264 @@
+//! Results of slow modules kept between runs under `$XDG_CACHE_HOME/rfetch`.
+//!
+//! An entry is reused while it is younger than the module's TTL and the fingerprint of its
+//! invalidation trigger (boot id, package database mtimes) still matches the one it was stored
+//! with. The cache is never consulted under a sysroot.
+//!
+//! Versions probed by running a program (`bash --version`) are cached separately, per binary,
+//! and reused until the binary changes; see [`version`].
+
+use serde::{Deserialize, Serialize};
+use std::cell::Cell;
+use std::ffi::OsStr;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::config::Config;
+use crate::error::RFetchError;
+use crate::modules::{AnyModule, ModuleValue};
+use crate::sysroot;
+use crate::trace::trace;
+use crate::utils::find_in_path;
+
+/// How long a probed version is trusted while its binary stays the same file. Proxies such as
+/// rustup or pyenv shims keep the same binary across switches.
+const VERSION_TTL: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+thread_local! {
+    /// Set while a module collects with caching allowed; `Some(true)` under `--refresh`.
+    static VERSIONS: Cell<Option<bool>> = const { Cell::new(None) };
+}
+
+#[derive(Debug, Clone)]
+pub struct CachePolicy {
+    pub ttl: Duration,
+    pub invalidate: Invalidate,
+}
+
+#[derive(Debug, Clone)]
+pub enum Invalidate {
+    /// Stale after a reboot; for hardware and the running kernel.
+    Boot,
+    /// Stale as soon as any of these files or directories is modified, or one appears or goes away.
+    Modified(Vec<PathBuf>),
+    /// Stale when the source a value was read from changes: a custom module's command line or
+    /// file path, or the file itself being modified.
+    Source { source: String, files: Vec<PathBuf> },
+}
+
+/// What a cache hit reports back to the gather loop.
+#[derive(Debug, Clone, Copy)]
+pub struct Hit {
+    pub age: Duration,
+}
+
+#[derive(Serialize, Deserialize)]
+struct VersionEntry {
+    fingerprint: String,
+    stored: u64,
+    version: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Entry {
+    fingerprint: String,
+    stored: u64,
+    value: Option<serde_json::Value>,
+}
+
+pub fn dir() -> Option<PathBuf> {
+    dirs::cache_dir().map(|dir| dir.join("rfetch"))
+}
+
+/// Collects `module`, answering from the cache when its policy allows. Errors are never cached.
+pub fn collect(
+    module: &dyn AnyModule,
+    config: &Config,
+) -> (Result<Option<ModuleValue>, RFetchError>, Option<Hit>) {
+    let caching = config.cache.enabled && sysroot::is_live();
+    let previous = VERSIONS.with(|versions| versions.replace(caching.then_some(config.cache.refresh)));
+    let result = collect_module(module, config);
+    VERSIONS.with(|versions| versions.set(previous));
+    result
+}
+
+fn collect_module(
+    module: &dyn AnyModule,
+    config: &Config,
+) -> (Result<Option<ModuleValue>, RFetchError>, Option<Hit>) {
+    let policy = match policy(module, config) {
+        Some(policy) => policy,
+        None => return (module.collect(config), None),
+    };
+
+    let path = entry_path(module.id());
+    let fingerprint = policy.invalidate.fingerprint();
+    let now = now();
+
+    if !config.cache.refresh {
+        if let Some(entry) = path.as_ref().and_then(|path| fs::read_to_string(path).ok()) {
+            if let Ok(entry) = serde_json::from_str::<Entry>(&entry) {
+                let age = now.saturating_sub(entry.stored);
+                if entry.fingerprint == fingerprint && age < policy.ttl.as_secs() {
+                    let value = match entry.value {
+                        Some(json) => module.restore(json).map(Some),
+                        None => Some(None),
+                    };
+                    // An entry written by an older rfetch may no longer deserialize; recollect.
+                    if let Some(value) = value {
+                        trace!("cache hit, {}s old", age);
+                        return (Ok(value), Some(Hit { age: Duration::from_secs(age) }));
+                    }
+                    trace!("cache entry no longer deserializes");
+                } else if entry.fingerprint != fingerprint {
+                    trace!("cache entry invalidated ({} -> {})", entry.fingerprint, fingerprint);
+                } else {
+                    trace!("cache entry expired, {}s old", age);
+                }
+            }
+        }
+    }
+
+    let outcome = module.collect(config);
+    if let (Ok(value), Some(path)) = (&outcome, path) {
+        let entry = Entry {
+            fingerprint,
+            stored: now,
+            value: value.as_ref().and_then(|value| module.store(value)),
+        };
+        write(&path, &entry);
+    }
+    (outcome, None)
+}
+
+/// The version `probe` finds by running `program`, reused from an earlier run while the binary is
+/// unchanged. Outside [`collect`], with `--no-cache` or under a sysroot, this just probes.
+pub fn version<F>(program: &OsStr, args: &[&str], probe: F) -> Option<String>
+where
+    F: FnOnce() -> Option<String>,
+{
+    let refresh = match VERSIONS.with(|versions| versions.get()) {
+        Some(refresh) => refresh,
+        None => return probe(),
+    };
+    let binary = if Path::new(program).is_absolute() {
+        Some(PathBuf::from(program))
+    } else {
+        program.to_str().and_then(find_in_path)
+    };
+    let (binary, dir) = match (binary, dir()) {
+        (Some(binary), Some(dir)) => (binary, dir),
+        _ => return probe(),
+    };
+    let key: Vec<String> = std::iter::once(binary.to_string_lossy().to_string())
+        .chain(args.iter().map(|arg| arg.to_string()))
+        .collect();
+    let path = dir.join("versions").join(format!("{}.json", file_name(&key.join(" "))));
+    let fingerprint = Invalidate::Modified(vec![binary.clone()]).fingerprint();
+    let now = now();
+
+    if !refresh {
+        let entry = fs::read_to_string(&path).ok();
+        if let Some(entry) = entry.and_then(|entry| serde_json::from_str::<VersionEntry>(&entry).ok()) {
+            if entry.fingerprint == fingerprint && now.saturating_sub(entry.stored) < VERSION_TTL.as_secs() {
+                trace!("cached version of {}: {}", binary.display(), entry.version);
+                return Some(entry.version);
+            }
+        }
+    }
+
+    // A failed probe is not stored: it may have timed out, and is retried next run.
+    let version = probe()?;
+    write(&path, &VersionEntry { fingerprint, stored: now, version: version.clone() });
+    Some(version)
+}
+
+fn write<T: Serialize>(path: &Path, entry: &T) {
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    if let Ok(content) = serde_json::to_string(entry) {
+        let _ = fs::write(path, content);
+    }
+}
+
+/// The module's policy with the `[cache.ttl]` override applied, or `None` when caching is off
+/// for it (no policy, a TTL of 0, `--no-cache`, or a sysroot).
+fn policy(module: &dyn AnyModule, config: &Config) -> Option<CachePolicy> {
+    if !config.cache.enabled || !sysroot::is_live() {
+        return None;
+    }
+    let mut policy = module.cache(config)?;
+    if let Some(secs) = config.cache.ttl.get(module.id()) {
+        policy.ttl = Duration::from_secs(*secs);
+    }
+    (!policy.ttl.is_zero()).then_some(policy)
+}
+
+fn entry_path(id: &str) -> Option<PathBuf> {
+    dir().map(|dir| dir.join("modules").join(format!("{}.json", file_name(id))))
+}
+
+fn file_name(key: &str) -> String {
+    key.chars().map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' }).collect()
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+impl Invalidate {
+    pub fn fingerprint(&self) -> String {
+        match self {
+            // Without a boot id (non-Linux) only the TTL expires the entry.
+            Invalidate::Boot => sysroot::read_to_string("/proc/sys/kernel/random/boot_id")
+                .map(|id| id.trim().to_string())
+                .unwrap_or_default(),
+            Invalidate::Modified(paths) => paths
+                .iter()
+                .map(|path| {
+                    fs::metadata(sysroot::path(path))
+                        .and_then(|meta| meta.modified())
+                        .ok()
+                        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                        .map(|d| format!("{}.{}", d.as_secs(), d.subsec_nanos()))
+                        .unwrap_or_else(|| "-".to_string())
+                })
+                .collect::<Vec<_>>()
+                .join(","),
+            Invalidate::Source { source, files } => {
+                format!("{}@{}", source, Invalidate::Modified(files.clone()).fingerprint())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_modified_fingerprint_tracks_mtime() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("var/lib/dpkg")).unwrap();
+        let status = dir.path().join("var/lib/dpkg/status");
+        fs::write(&status, "Package: a\n").unwrap();
+
+        let _scope = sysroot::enter(Some(dir.path().to_path_buf()));
+        let trigger = Invalidate::Modified(vec!["/var/lib/dpkg/status".into(), "/var/lib/pacman/local".into()]);
+        let before = trigger.fingerprint();
+        assert!(before.ends_with(",-"));
+
+        let file = fs::File::options().write(true).open(&status).unwrap();
+        file.set_modified(SystemTime::now() + Duration::from_secs(60)).unwrap();
+        assert_ne!(trigger.fingerprint(), before);
+    }
+
+    #[test]
+    fn test_source_fingerprint_tracks_source() {
+        let command = |source: &str| Invalidate::Source { source: source.to_string(), files: Vec::new() };
+        assert_eq!(command("command:date").fingerprint(), command("command:date").fingerprint());
+        assert_ne!(command("command:date").fingerprint(), command("command:uptime").fingerprint());
+    }
+}
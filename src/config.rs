@@ -1,15 +1,39 @@
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
+use crate::datetime::DEFAULT_DATE_FORMAT;
 use crate::error::RFetchError;
-use crate::themes::AdvancedColor;
+use crate::modules::{self, AnyModule, Detail};
+use crate::themes::{AdvancedColor, Theme};
+use crate::utils::suggest;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
     pub display: DisplayConfig,
     pub info: InfoConfig,
     pub colors: ColorConfig,
+    #[serde(default)]
+    pub sensors: SensorsConfig,
+    #[serde(default)]
+    pub date: DateConfig,
+    #[serde(default)]
+    pub uptime: UptimeConfig,
+    #[serde(default)]
+    pub toolchains: ToolchainsConfig,
+    #[serde(default)]
+    pub timeouts: TimeoutsConfig,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub custom: Vec<CustomModuleConfig>,
+    #[serde(default)]
+    pub plugins: PluginsConfig,
+    #[serde(default)]
+    pub cache: CacheConfig,
+    /// Captured system tree to read instead of the live one; set by `--sysroot`, never from a file.
+    #[serde(skip)]
+    pub sysroot: Option<PathBuf>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -21,33 +45,44 @@ pub struct DisplayConfig {
     pub verbose: bool,
     pub separator: String,
     pub padding: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub theme: Option<String>,
+    /// Report per-module timings: a table on stderr, or `_meta` in JSON output.
+    #[serde(default)]
+    pub timings: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct InfoConfig {
-    pub show_os: bool,
-    pub show_kernel: bool,
-    pub show_uptime: bool,
-    pub show_packages: bool,
-    pub show_shell: bool,
-    pub show_resolution: bool,
-    pub show_de: bool,
-    pub show_wm: bool,
-    pub show_theme: bool,
-    pub show_icons: bool,
-    pub show_font: bool,
-    pub show_cursor: bool,
-    pub show_terminal: bool,
-    pub show_cpu: bool,
-    pub show_gpu: bool,
-    pub show_memory: bool,
-    pub show_disk: bool,
-    pub show_battery: bool,
-    pub show_locale: bool,
-    pub show_local_ip: bool,
-    pub show_public_ip: bool,
-    pub show_users: bool,
-    pub show_date: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub order: Vec<String>,
+    #[serde(flatten)]
+    pub toggles: BTreeMap<String, bool>,
+}
+
+impl InfoConfig {
+    pub fn is_enabled(&self, module: &dyn AnyModule) -> bool {
+        self.toggles
+            .get(&format!("show_{}", module.id()))
+            .copied()
+            .unwrap_or_else(|| module.default_enabled())
+    }
+
+    pub fn set_enabled(&mut self, id: &str, enabled: bool) {
+        self.toggles.insert(format!("show_{}", id), enabled);
+    }
+}
+
+impl Default for InfoConfig {
+    fn default() -> Self {
+        Self {
+            order: Vec::new(),
+            toggles: modules::MODULES
+                .iter()
+                .map(|module| (format!("show_{}", module.id()), module.default_enabled()))
+                .collect(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -58,6 +93,180 @@ pub struct ColorConfig {
     pub value: AdvancedColor,
     pub separator: AdvancedColor,
     pub logo: AdvancedColor,
+    #[serde(default = "default_warning_color")]
+    pub warning: AdvancedColor,
+    #[serde(default = "default_error_color")]
+    pub error: AdvancedColor,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SensorsConfig {
+    pub warning_temp: f64,
+    pub critical_temp: f64,
+    pub show_fans: bool,
+}
+
+impl Default for SensorsConfig {
+    fn default() -> Self {
+        Self {
+            warning_temp: 70.0,
+            critical_temp: 85.0,
+            show_fans: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DateConfig {
+    pub format: String,
+    pub show_timezone: bool,
+    pub show_utc_offset: bool,
+    pub show_week: bool,
+}
+
+impl Default for DateConfig {
+    fn default() -> Self {
+        Self {
+            format: DEFAULT_DATE_FORMAT.to_string(),
+            show_timezone: false,
+            show_utc_offset: false,
+            show_week: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UptimeConfig {
+    pub format: String,
+}
+
+impl Default for UptimeConfig {
+    fn default() -> Self {
+        Self {
+            format: "short".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ToolchainsConfig {
+    pub probes: Vec<String>,
+    pub timeout_ms: u64,
+    pub cache_ttl: u64,
+    pub render: String,
+    pub title: String,
+}
+
+impl Default for ToolchainsConfig {
+    fn default() -> Self {
+        Self {
+            probes: ["rust", "cargo", "python", "node", "go", "java", "gcc", "clang"]
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+            timeout_ms: 1000,
+            cache_ttl: 86400,
+            render: "inline".to_string(),
+            title: "Toolchains".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TimeoutsConfig {
+    pub default_ms: u64,
+    pub modules: HashMap<String, u64>,
+}
+
+impl TimeoutsConfig {
+    pub fn for_module(&self, id: &str) -> Duration {
+        Duration::from_millis(self.modules.get(id).copied().unwrap_or(self.default_ms))
+    }
+}
+
+impl Default for TimeoutsConfig {
+    fn default() -> Self {
+        Self {
+            default_ms: 2000,
+            modules: [("packages", 5000), ("toolchains", 3000)]
+                .iter()
+                .map(|(id, ms)| (id.to_string(), *ms))
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CustomModuleConfig {
+    pub id: String,
+    pub label: String,
+    #[serde(default)]
+    pub command: Option<String>,
+    #[serde(default)]
+    pub file: Option<String>,
+    #[serde(default)]
+    pub regex: Option<String>,
+    #[serde(default = "default_custom_timeout")]
+    pub timeout_ms: u64,
+    #[serde(default)]
+    pub cache_ttl: u64,
+}
+
+fn default_custom_timeout() -> u64 {
+    1000
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PluginsConfig {
+    pub enabled: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dir: Option<String>,
+    pub timeout_ms: u64,
+}
+
+impl Default for PluginsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            dir: None,
+            timeout_ms: 2000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CacheConfig {
+    pub enabled: bool,
+    /// Per-module TTL overrides in seconds; 0 disables caching for that module.
+    pub ttl: HashMap<String, u64>,
+    /// Recollect everything but still store the fresh values; set by `--refresh`.
+    #[serde(skip)]
+    pub refresh: bool,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            ttl: HashMap::new(),
+            refresh: false,
+        }
+    }
+}
+
+fn default_warning_color() -> AdvancedColor {
+    AdvancedColor::from("yellow")
+}
+
+fn default_error_color() -> AdvancedColor {
+    AdvancedColor::from("red")
 }
 
 impl Default for Config {
@@ -71,32 +280,10 @@ impl Default for Config {
                 verbose: false,
                 separator: ": ".to_string(),
                 padding: 2,
+                theme: None,
+                timings: false,
             },
-            info: InfoConfig {
-                show_os: true,
-                show_kernel: true,
-                show_uptime: true,
-                show_packages: true,
-                show_shell: true,
-                show_resolution: true,
-                show_de: true,
-                show_wm: true,
-                show_theme: false,
-                show_icons: false,
-                show_font: false,
-                show_cursor: false,
-                show_terminal: true,
-                show_cpu: true,
-                show_gpu: true,
-                show_memory: true,
-                show_disk: true,
-                show_battery: true,
-                show_locale: false,
-                show_local_ip: false,
-                show_public_ip: false,
-                show_users: false,
-                show_date: true,
-            },
+            info: InfoConfig::default(),
             colors: ColorConfig {
                 title: AdvancedColor::from("cyan"),
                 subtitle: AdvancedColor::from("blue"),
@@ -104,7 +291,18 @@ impl Default for Config {
                 value: AdvancedColor::from("white"),
                 separator: AdvancedColor::from("white"),
                 logo: AdvancedColor::from("cyan"),
+                warning: default_warning_color(),
+                error: default_error_color(),
             },
+            sensors: SensorsConfig::default(),
+            date: DateConfig::default(),
+            uptime: UptimeConfig::default(),
+            toolchains: ToolchainsConfig::default(),
+            timeouts: TimeoutsConfig::default(),
+            custom: Vec::new(),
+            plugins: PluginsConfig::default(),
+            cache: CacheConfig::default(),
+            sysroot: None,
         }
     }
 }
@@ -144,7 +342,8 @@ impl Config {
         Ok(())
     }
 
-    fn default_config_path() -> Result<String, RFetchError> {
+    /// `<config dir>/rfetch/config.toml`, used when no `--config` is given.
+    pub fn default_config_path() -> Result<String, RFetchError> {
         let config_dir = dirs::config_dir()
             .ok_or_else(|| RFetchError::config("Could not find config directory"))?;
         
@@ -155,25 +354,98 @@ impl Config {
             .to_string())
     }
 
+    /// Copies a theme's colors and display settings over the configured ones.
+    pub fn apply_theme(&mut self, theme: &Theme) {
+        self.colors.title = theme.colors.title.clone();
+        self.colors.subtitle = theme.colors.subtitle.clone();
+        self.colors.key = theme.colors.key.clone();
+        self.colors.value = theme.colors.value.clone();
+        self.colors.separator = theme.colors.separator.clone();
+        self.colors.logo = theme.colors.logo.clone();
+        if let Some(warning) = &theme.colors.warning {
+            self.colors.warning = warning.clone();
+        }
+        if let Some(error) = &theme.colors.error {
+            self.colors.error = error.clone();
+        }
+
+        self.display.logo_type = theme.display.logo_type.clone();
+        self.display.separator = theme.display.separator.clone();
+        self.display.padding = theme.display.padding;
+    }
+
     pub fn apply_minimal(&mut self) {
-        self.info.show_theme = false;
-        self.info.show_icons = false;
-        self.info.show_font = false;
-        self.info.show_cursor = false;
-        self.info.show_locale = false;
-        self.info.show_local_ip = false;
-        self.info.show_public_ip = false;
-        self.info.show_users = false;
+        for module in modules::MODULES.iter() {
+            if module.detail() != Detail::Basic {
+                self.info.set_enabled(module.id(), false);
+            }
+        }
         self.display.logo_type = "small".to_string();
     }
 
     pub fn apply_verbose(&mut self) {
-        self.info.show_theme = true;
-        self.info.show_icons = true;
-        self.info.show_font = true;
-        self.info.show_cursor = true;
-        self.info.show_locale = true;
-        self.info.show_local_ip = true;
-        self.info.show_users = true;
-    }
-}
\ No newline at end of file
+        for module in modules::MODULES.iter() {
+            if module.detail() == Detail::Verbose {
+                self.info.set_enabled(module.id(), true);
+            }
+        }
+    }
+
+    /// Applies `--modules`, `--show` and `--hide`: a non-empty `only` enables exactly those
+    /// modules in that order, then `show` and `hide` toggle single modules.
+    pub fn select_modules(&mut self, only: &[String], show: &[String], hide: &[String]) -> Result<(), RFetchError> {
+        let ids: Vec<String> = modules::registry(self).iter().map(|module| module.id().to_string()).collect();
+        for id in only.iter().chain(show).chain(hide) {
+            if !ids.contains(id) {
+                let message = match suggest(id, ids.iter().map(String::as_str)) {
+                    Some(suggestion) => format!("Unknown module '{}'. Did you mean '{}'?", id, suggestion),
+                    None => format!("Unknown module '{}'. Use `rfetch --list-modules` to see available modules.", id),
+                };
+                return Err(RFetchError::config(message));
+            }
+        }
+
+        if !only.is_empty() {
+            for id in &ids {
+                self.info.set_enabled(id, only.contains(id));
+            }
+            self.info.order = only.to_vec();
+        }
+        for id in show {
+            self.info.set_enabled(id, true);
+        }
+        for id in hide {
+            self.info.set_enabled(id, false);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_example_config_parses() {
+        let config: Config = toml::from_str(include_str!("../config.example.toml")).unwrap();
+        assert_eq!(config.display.logo_type, Config::default().display.logo_type);
+        // Colors may be plain names instead of tables.
+        assert_eq!(config.colors.title.base, "cyan");
+    }
+
+    #[test]
+    fn test_select_modules() {
+        let mut config = Config::default();
+        let ids = |ids: &[&str]| ids.iter().map(|id| id.to_string()).collect::<Vec<_>>();
+        config.select_modules(&ids(&["memory", "disk"]), &ids(&["os"]), &ids(&["disk"])).unwrap();
+        let enabled: Vec<&str> = modules::MODULES.iter()
+            .filter(|module| config.info.is_enabled(module.as_ref()))
+            .map(|module| module.id())
+            .collect();
+        assert_eq!(enabled, ["os", "memory"]);
+        assert_eq!(config.info.order, ["memory", "disk"]);
+
+        let error = config.select_modules(&[], &[], &ids(&["kernal"])).unwrap_err();
+        assert!(error.to_string().contains("Did you mean 'kernel'?"));
+    }
+}
@@ -0,0 +1,194 @@
+use regex::Regex;
+use std::io;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::cache::{CachePolicy, Invalidate};
+use crate::config::{Config, CustomModuleConfig};
+use crate::display::DisplayManager;
+use crate::error::RFetchError;
+use crate::modules::{Module, ModuleHandle, Registered};
+use crate::sysroot;
+
+pub struct CustomModule {
+    config: CustomModuleConfig,
+}
+
+pub fn custom_modules(config: &Config) -> Vec<ModuleHandle> {
+    config
+        .custom
+        .iter()
+        .map(|custom| Arc::new(Registered(CustomModule { config: custom.clone() })) as ModuleHandle)
+        .collect()
+}
+
+impl Module for CustomModule {
+    type Value = String;
+
+    fn id(&self) -> &str {
+        &self.config.id
+    }
+
+    fn label(&self) -> &str {
+        &self.config.label
+    }
+
+    fn timeout(&self) -> Option<Duration> {
+        // Leave room for the regex after the command itself gives up.
+        Some(Duration::from_millis(self.config.timeout_ms + 100))
+    }
+
+    /// Reused for `cache_ttl` seconds, or until the command line or file changes.
+    fn cache(&self, _config: &Config) -> Option<CachePolicy> {
+        let source = self.source().ok()?;
+        Some(CachePolicy {
+            ttl: Duration::from_secs(self.config.cache_ttl),
+            invalidate: Invalidate::Source { source, files: self.file_path().into_iter().collect() },
+        })
+    }
+
+    fn collect(&self, _config: &Config) -> Result<Option<String>, RFetchError> {
+        self.source()?;
+        match self.read_raw()? {
+            Some(raw) => extract(&raw, self.config.regex.as_deref()),
+            None => Ok(None),
+        }
+    }
+
+    fn render(&self, value: &String, display: &DisplayManager) -> Vec<String> {
+        vec![display.format_info_line(&self.config.label, value)]
+    }
+}
+
+impl CustomModule {
+    fn source(&self) -> Result<String, RFetchError> {
+        match (&self.config.command, &self.config.file) {
+            (Some(command), None) => Ok(format!("command:{}", command)),
+            (None, Some(file)) => Ok(format!("file:{}", file)),
+            _ => Err(RFetchError::config(format!(
+                "custom module '{}' needs exactly one of `command` or `file`",
+                self.config.id
+            ))),
+        }
+    }
+
+    /// Returns `None` when the command exits unsuccessfully or the file is missing, which is
+    /// treated as "nothing to show" (e.g. `git` outside a repository) rather than an error.
+    fn read_raw(&self) -> Result<Option<String>, RFetchError> {
+        if let Some(path) = self.file_path() {
+            return Ok(sysroot::read_to_string(path).ok());
+        }
+
+        let command = self.config.command.as_deref().unwrap_or_default();
+        let timeout = Duration::from_millis(self.config.timeout_ms);
+        let output = match sysroot::run_with_timeout(SHELL, &[SHELL_FLAG, command], timeout) {
+            Ok(output) => output,
+            // A sysroot without a stub for the command, like a shell that cannot find it.
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(_) => return Err(RFetchError::system_info(format!("`{}` failed or timed out", command))),
+        };
+
+        if !output.status.success() {
+            return Ok(None);
+        }
+        Ok(Some(String::from_utf8_lossy(&output.stdout).to_string()))
+    }
+
+    fn file_path(&self) -> Option<PathBuf> {
+        let file = self.config.file.as_ref()?;
+        Some(match file.strip_prefix("~/") {
+            Some(rest) => dirs::home_dir().map(|home| home.join(rest)).unwrap_or_else(|| PathBuf::from(file)),
+            None => PathBuf::from(file),
+        })
+    }
+}
+
+#[cfg(unix)]
+const SHELL: &str = "sh";
+#[cfg(unix)]
+const SHELL_FLAG: &str = "-c";
+
+#[cfg(windows)]
+const SHELL: &str = "cmd";
+#[cfg(windows)]
+const SHELL_FLAG: &str = "/C";
+
+/// Applies the optional regex: the first capture group if there is one, otherwise the whole
+/// match. Without a regex the first non-empty line is used.
+fn extract(raw: &str, pattern: Option<&str>) -> Result<Option<String>, RFetchError> {
+    let value = match pattern {
+        Some(pattern) => {
+            let regex = Regex::new(pattern)
+                .map_err(|e| RFetchError::config(format!("invalid regex '{}': {}", pattern, e)))?;
+            regex
+                .captures(raw)
+                .and_then(|captures| captures.get(1).or_else(|| captures.get(0)))
+                .map(|m| m.as_str().trim().to_string())
+        }
+        None => raw.lines().map(str::trim).find(|line| !line.is_empty()).map(|line| line.to_string()),
+    };
+
+    Ok(value.filter(|value| !value.is_empty()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract() {
+        assert_eq!(extract("\n  main\n", None).unwrap(), Some("main".to_string()));
+        assert_eq!(
+            extract("current-context: prod-eu\n", Some(r"current-context:\s*(\S+)")).unwrap(),
+            Some("prod-eu".to_string())
+        );
+        assert_eq!(extract("v1.2.3", Some(r"\d+\.\d+")).unwrap(), Some("1.2".to_string()));
+        assert_eq!(extract("nothing", Some(r"\d+")).unwrap(), None);
+        assert!(extract("x", Some("(")).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_command_module() {
+        let module = CustomModule {
+            config: CustomModuleConfig {
+                id: "greeting".to_string(),
+                label: "Greeting".to_string(),
+                command: Some("echo 'hello world'".to_string()),
+                file: None,
+                regex: Some(r"hello (\w+)".to_string()),
+                timeout_ms: 1000,
+                cache_ttl: 0,
+            },
+        };
+        assert_eq!(module.collect(&Config::default()).unwrap(), Some("world".to_string()));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_sources_follow_sysroot() {
+        let root = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(root.path().join("etc")).unwrap();
+        std::fs::write(root.path().join("etc/motd"), "from the fixture\n").unwrap();
+        std::fs::create_dir_all(root.path().join(sysroot::COMMANDS_DIR)).unwrap();
+        std::fs::write(root.path().join(sysroot::COMMANDS_DIR).join("sh -c hostname"), "fixture-host\n").unwrap();
+        let _scope = sysroot::enter(Some(root.path().to_path_buf()));
+
+        let module = |command: Option<&str>, file: Option<&str>| CustomModule {
+            config: CustomModuleConfig {
+                id: "source".to_string(),
+                label: "Source".to_string(),
+                command: command.map(str::to_string),
+                file: file.map(str::to_string),
+                regex: None,
+                timeout_ms: 1000,
+                cache_ttl: 0,
+            },
+        };
+        let config = Config::default();
+        assert_eq!(module(None, Some("/etc/motd")).collect(&config).unwrap(), Some("from the fixture".to_string()));
+        assert_eq!(module(Some("hostname"), None).collect(&config).unwrap(), Some("fixture-host".to_string()));
+        assert_eq!(module(Some("uname -r"), None).collect(&config).unwrap(), None);
+    }
+}
@@ -0,0 +1,81 @@
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Datelike, Local};
+use serde::{Deserialize, Serialize};
+
+use crate::config::DateConfig;
+use crate::sysroot;
+
+pub const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DateInfo {
+    pub formatted: String,
+    pub timestamp: DateTime<Local>,
+    pub timezone: Option<String>,
+    pub utc_offset: String,
+    pub iso_week: u32,
+}
+
+pub fn get_date_info(config: &DateConfig) -> DateInfo {
+    let now = Local::now();
+    let format = if is_valid_format(&config.format) {
+        config.format.as_str()
+    } else {
+        DEFAULT_DATE_FORMAT
+    };
+
+    DateInfo {
+        formatted: now.format(format).to_string(),
+        timestamp: now,
+        timezone: get_timezone(),
+        utc_offset: now.format("%:z").to_string(),
+        iso_week: now.iso_week().week(),
+    }
+}
+
+pub fn is_valid_format(format: &str) -> bool {
+    !format.is_empty() && StrftimeItems::new(format).all(|item| !matches!(item, Item::Error))
+}
+
+pub fn get_timezone() -> Option<String> {
+    if let Ok(tz) = sysroot::var("TZ") {
+        let tz = tz.trim_start_matches(':');
+        if !tz.is_empty() {
+            return Some(zone_name_from_path(tz).unwrap_or(tz).to_string());
+        }
+    }
+
+    if let Ok(target) = sysroot::read_link("/etc/localtime") {
+        if let Some(zone) = zone_name_from_path(&target.to_string_lossy()) {
+            return Some(zone.to_string());
+        }
+    }
+
+    sysroot::read_to_string("/etc/timezone")
+        .ok()
+        .map(|tz| tz.trim().to_string())
+        .filter(|tz| !tz.is_empty())
+}
+
+fn zone_name_from_path(path: &str) -> Option<&str> {
+    path.split_once("zoneinfo/").map(|(_, zone)| zone)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_validation() {
+        assert!(is_valid_format("%A %d %B %Y"));
+        assert!(!is_valid_format("%Y-%Q"));
+        assert!(!is_valid_format(""));
+    }
+
+    #[test]
+    fn test_zone_name_from_path() {
+        assert_eq!(zone_name_from_path("/usr/share/zoneinfo/Europe/Rome"), Some("Europe/Rome"));
+        assert_eq!(zone_name_from_path("../usr/share/zoneinfo/UTC"), Some("UTC"));
+        assert_eq!(zone_name_from_path("Europe/Rome"), None);
+    }
+}
@@ -0,0 +1,251 @@
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::sync::{Arc, OnceLock};
+
+use crate::process;
+use crate::sysroot;
+use crate::trace::trace;
+use crate::utils::{probe_version, with_version};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DesktopInfo {
+    pub session_type: String,
+    pub desktop_environment: Option<String>,
+    pub window_manager: Option<String>,
+}
+
+struct DesktopRule {
+    process: &'static str,
+    name: &'static str,
+    version_command: Option<(&'static str, &'static [&'static str])>,
+}
+
+const fn rule(
+    process: &'static str,
+    name: &'static str,
+    version_command: Option<(&'static str, &'static [&'static str])>,
+) -> DesktopRule {
+    DesktopRule { process, name, version_command }
+}
+
+const DE_RULES: &[DesktopRule] = &[
+    rule("gnome-shell", "GNOME", Some(("gnome-shell", &["--version"]))),
+    rule("plasmashell", "KDE Plasma", Some(("plasmashell", &["--version"]))),
+    rule("xfce4-session", "Xfce", Some(("xfce4-session", &["--version"]))),
+    rule("cinnamon", "Cinnamon", Some(("cinnamon", &["--version"]))),
+    rule("cinnamon-session", "Cinnamon", Some(("cinnamon", &["--version"]))),
+    rule("mate-session", "MATE", Some(("mate-session", &["--version"]))),
+    rule("lxqt-session", "LXQt", Some(("lxqt-session", &["--version"]))),
+    rule("lxsession", "LXDE", None),
+    rule("budgie-panel", "Budgie", Some(("budgie-desktop", &["--version"]))),
+    rule("gala", "Pantheon", Some(("gala", &["--version"]))),
+    rule("cosmic-session", "COSMIC", None),
+    rule("dde-session", "Deepin", None),
+    rule("enlightenment", "Enlightenment", Some(("enlightenment", &["--version"]))),
+];
+
+const WM_RULES: &[DesktopRule] = &[
+    rule("Hyprland", "Hyprland", Some(("hyprctl", &["version"]))),
+    rule("sway", "Sway", Some(("sway", &["--version"]))),
+    rule("river", "river", Some(("river", &["-version"]))),
+    rule("niri", "niri", Some(("niri", &["--version"]))),
+    rule("wayfire", "Wayfire", Some(("wayfire", &["--version"]))),
+    rule("labwc", "labwc", Some(("labwc", &["--version"]))),
+    rule("hikari", "hikari", Some(("hikari", &["-v"]))),
+    rule("dwl", "dwl", Some(("dwl", &["-v"]))),
+    rule("weston", "Weston", Some(("weston", &["--version"]))),
+    rule("cosmic-comp", "cosmic-comp", None),
+    rule("kwin_wayland", "KWin", Some(("kwin_wayland", &["--version"]))),
+    rule("kwin_x11", "KWin", Some(("kwin_x11", &["--version"]))),
+    rule("gnome-shell", "Mutter", Some(("mutter", &["--version"]))),
+    rule("mutter", "Mutter", Some(("mutter", &["--version"]))),
+    rule("cinnamon", "Muffin", None),
+    rule("xfwm4", "Xfwm4", Some(("xfwm4", &["--version"]))),
+    rule("marco", "Marco", Some(("marco", &["--version"]))),
+    rule("metacity", "Metacity", Some(("metacity", &["--version"]))),
+    rule("gala", "Gala", Some(("gala", &["--version"]))),
+    rule("i3", "i3", Some(("i3", &["--version"]))),
+    rule("bspwm", "bspwm", Some(("bspwm", &["-v"]))),
+    rule("dwm", "dwm", Some(("dwm", &["-v"]))),
+    rule("awesome", "awesome", Some(("awesome", &["--version"]))),
+    rule("xmonad", "xmonad", Some(("xmonad", &["--version"]))),
+    rule("openbox", "Openbox", Some(("openbox", &["--version"]))),
+    rule("fluxbox", "Fluxbox", Some(("fluxbox", &["-version"]))),
+    rule("blackbox", "Blackbox", Some(("blackbox", &["-version"]))),
+    rule("fvwm", "FVWM", Some(("fvwm", &["--version"]))),
+    rule("fvwm3", "FVWM3", Some(("fvwm3", &["--version"]))),
+    rule("jwm", "JWM", Some(("jwm", &["-v"]))),
+    rule("icewm", "IceWM", Some(("icewm", &["--version"]))),
+    rule("herbstluftwm", "herbstluftwm", Some(("herbstluftwm", &["--version"]))),
+    rule("qtile", "Qtile", Some(("qtile", &["--version"]))),
+    rule("spectrwm", "spectrwm", None),
+    rule("leftwm", "LeftWM", Some(("leftwm", &["--version"]))),
+    rule("cwm", "cwm", None),
+    rule("2bwm", "2bwm", None),
+    rule("compiz", "Compiz", Some(("compiz", &["--version"]))),
+    rule("enlightenment", "Enlightenment", Some(("enlightenment", &["--version"]))),
+];
+
+thread_local! {
+    static SHARED: RefCell<Option<Arc<OnceLock<DesktopInfo>>>> = const { RefCell::new(None) };
+}
+
+/// Restores the thread's previous shared scan when dropped.
+pub struct Shared {
+    previous: Option<Arc<OnceLock<DesktopInfo>>>,
+}
+
+/// Lets the DE and WM modules, which run on different gather workers, share one process scan.
+/// Each gather passes a fresh `scan`, so a later gather sees a changed session.
+pub fn share(scan: Arc<OnceLock<DesktopInfo>>) -> Shared {
+    Shared { previous: SHARED.with(|shared| shared.replace(Some(scan))) }
+}
+
+impl Drop for Shared {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        SHARED.with(|shared| *shared.borrow_mut() = previous);
+    }
+}
+
+pub fn detect_desktop() -> DesktopInfo {
+    match SHARED.with(|shared| shared.borrow().clone()) {
+        Some(scan) => scan.get_or_init(scan_desktop).clone(),
+        None => scan_desktop(),
+    }
+}
+
+fn scan_desktop() -> DesktopInfo {
+    let running: HashSet<String> = process::list_processes()
+        .iter()
+        .map(|p| normalize_process_name(&p.name()))
+        .collect();
+
+    let current_desktop = sysroot::var("XDG_CURRENT_DESKTOP")
+        .or_else(|_| sysroot::var("DESKTOP_SESSION"))
+        .unwrap_or_default();
+
+    let window_manager = find_rule(WM_RULES, &running)
+        .inspect(|rule| trace!("window manager {} matched running process {}", rule.name, rule.process))
+        .or_else(|| {
+            current_desktop
+                .split(':')
+                .find_map(|name| WM_RULES.iter().find(|r| r.process.eq_ignore_ascii_case(name)))
+                .inspect(|rule| trace!("window manager {} named by the desktop session", rule.name))
+        })
+        .map(|rule| with_version(rule.name, rule_version(rule)))
+        .or_else(|| sysroot::var("WINDOW_MANAGER").ok().filter(|wm| !wm.is_empty()));
+
+    let desktop_environment = find_rule(DE_RULES, &running)
+        .inspect(|rule| trace!("desktop {} matched running process {}", rule.name, rule.process))
+        .or_else(|| {
+            desktop_from_env(&current_desktop)
+                .inspect(|rule| trace!("desktop {} named by the desktop session", rule.name))
+        })
+        .map(|rule| with_version(rule.name, rule_version(rule)));
+
+    DesktopInfo {
+        session_type: detect_session_type(),
+        desktop_environment,
+        window_manager,
+    }
+}
+
+pub fn detect_session_type() -> String {
+    match sysroot::var("XDG_SESSION_TYPE").unwrap_or_default().to_lowercase().as_str() {
+        "wayland" => return "Wayland".to_string(),
+        "x11" => return "X11".to_string(),
+        "tty" => return "TTY".to_string(),
+        _ => {}
+    }
+
+    if sysroot::var("WAYLAND_DISPLAY").map(|v| !v.is_empty()).unwrap_or(false) {
+        "Wayland".to_string()
+    } else if sysroot::var("DISPLAY").map(|v| !v.is_empty()).unwrap_or(false) {
+        "X11".to_string()
+    } else {
+        "TTY".to_string()
+    }
+}
+
+fn normalize_process_name(name: &str) -> String {
+    if name.starts_with("xmonad-") {
+        return "xmonad".to_string();
+    }
+    name.to_string()
+}
+
+fn find_rule(
+    rules: &'static [DesktopRule],
+    running: &HashSet<String>,
+) -> Option<&'static DesktopRule> {
+    rules.iter().find(|rule| running.contains(rule.process))
+}
+
+fn desktop_from_env(current_desktop: &str) -> Option<&'static DesktopRule> {
+    current_desktop.split(':').find_map(|name| {
+        let process = match name.to_lowercase().as_str() {
+            "gnome" | "gnome-classic" | "gnome-flashback" | "ubuntu" => "gnome-shell",
+            "kde" | "plasma" => "plasmashell",
+            "xfce" => "xfce4-session",
+            "x-cinnamon" | "cinnamon" => "cinnamon",
+            "mate" => "mate-session",
+            "lxqt" => "lxqt-session",
+            "lxde" => "lxsession",
+            "budgie" => "budgie-panel",
+            "pantheon" => "gala",
+            "cosmic" => "cosmic-session",
+            "deepin" => "dde-session",
+            _ => return None,
+        };
+        DE_RULES.iter().find(|rule| rule.process == process)
+    })
+}
+
+fn rule_version(rule: &DesktopRule) -> Option<String> {
+    let version = rule
+        .version_command
+        .and_then(|(program, args)| probe_version(program, args));
+
+    if version.is_none() && rule.process == "plasmashell" {
+        return sysroot::var("KDE_SESSION_VERSION").ok();
+    }
+
+    version
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_desktop_from_env() {
+        assert_eq!(desktop_from_env("ubuntu:GNOME").map(|r| r.name), Some("GNOME"));
+        assert_eq!(desktop_from_env("X-Cinnamon").map(|r| r.name), Some("Cinnamon"));
+        assert!(desktop_from_env("Hyprland").is_none());
+    }
+
+    #[test]
+    fn test_scan_is_shared_only_within_a_scope() {
+        let scan = Arc::new(OnceLock::new());
+        let _ = scan.set(DesktopInfo {
+            session_type: "Wayland".to_string(),
+            desktop_environment: Some("GNOME 46".to_string()),
+            window_manager: Some("Mutter 46".to_string()),
+        });
+        {
+            let _shared = share(Arc::clone(&scan));
+            assert_eq!(detect_desktop().desktop_environment.as_deref(), Some("GNOME 46"));
+        }
+        let root = tempfile::tempdir().unwrap();
+        let _scope = sysroot::enter(Some(root.path().to_path_buf()));
+        assert_ne!(detect_desktop().window_manager.as_deref(), Some("Mutter 46"));
+    }
+
+    #[test]
+    fn test_exact_process_match() {
+        let running: HashSet<String> = ["i3bar".to_string()].into_iter().collect();
+        assert!(find_rule(WM_RULES, &running).is_none());
+    }
+}
@@ -1,44 +1,72 @@
 use colored::*;
-use serde_json;
 use std::cmp;
+use std::io::{self, Write};
 use unicode_width::UnicodeWidthChar;
 
 use crate::config::Config;
 use crate::error::RFetchError;
-use crate::info::SystemInfo;
-use crate::logo::{get_logo, get_color_codes};
-use crate::themes::Theme;
-use crate::utils::*;
+use crate::info::{ModuleReport, ModuleStatus, SystemInfo, TIMEOUT};
+use crate::logo::get_logo;
+use crate::themes::{AdvancedColor, Theme};
+use crate::timings;
 
 pub struct DisplayManager<'a> {
     config: &'a Config,
     theme: Option<&'a Theme>,
+    terminal: bool,
 }
 
 impl<'a> DisplayManager<'a> {
     pub fn new(config: &'a Config) -> Self {
-        Self { config, theme: None }
+        Self { config, theme: None, terminal: atty::is(atty::Stream::Stdout) }
     }
 
     pub fn with_theme(config: &'a Config, theme: &'a Theme) -> Self {
-        Self { config, theme: Some(theme) }
+        Self { theme: Some(theme), ..Self::new(config) }
+    }
+
+    /// Tells `color_mode = "auto"` whether the sink is a terminal. Defaults to checking stdout.
+    pub fn with_terminal(mut self, terminal: bool) -> Self {
+        self.terminal = terminal;
+        self
     }
 
     pub fn display(&self, info: &SystemInfo) -> Result<(), RFetchError> {
+        self.render(info, &mut io::stdout().lock())
+    }
+
+    /// Renders the whole frame into memory and hands it to `out` in a single write and flush,
+    /// so a slow terminal never shows a half-drawn logo.
+    pub fn render<W: Write>(&self, info: &SystemInfo, out: &mut W) -> Result<(), RFetchError> {
+        let mut buffer = Vec::new();
         match self.config.display.output_format.as_str() {
-            "json" => self.display_json(info),
-            _ => self.display_normal(info),
+            "json" => self.display_json(info, &mut buffer)?,
+            _ => self.display_normal(info, &mut buffer)?,
         }
+        out.write_all(&buffer)?;
+        out.flush()?;
+        Ok(())
     }
 
-    fn display_json(&self, info: &SystemInfo) -> Result<(), RFetchError> {
-        let json = serde_json::to_string_pretty(info)?;
-        println!("{}", json);
+    pub fn render_to_string(&self, info: &SystemInfo) -> Result<String, RFetchError> {
+        let mut buffer = Vec::new();
+        self.render(info, &mut buffer)?;
+        String::from_utf8(buffer).map_err(|e| RFetchError::display(e.to_string()))
+    }
+
+    fn display_json(&self, info: &SystemInfo, out: &mut Vec<u8>) -> Result<(), RFetchError> {
+        let mut json = info.to_json();
+        if self.config.display.timings {
+            json["_meta"] = serde_json::json!({ "timings": timings::to_json(info) });
+        }
+        let json = serde_json::to_string_pretty(&json)?;
+        writeln!(out, "{}", json)?;
         Ok(())
     }
 
-    fn display_normal(&self, info: &SystemInfo) -> Result<(), RFetchError> {
-        let logo_lines = self.get_logo_lines(&info.os);
+    fn display_normal(&self, info: &SystemInfo, out: &mut Vec<u8>) -> Result<(), RFetchError> {
+        let os = info.get::<String>("os").map(|os| os.as_str()).unwrap_or("");
+        let logo_lines = self.get_logo_lines(os);
         let info_lines = self.build_info_lines(info);
 
         let max_logo_width = self.calculate_max_visual_width(&logo_lines);
@@ -49,13 +77,13 @@ impl<'a> DisplayManager<'a> {
             let separator = "─".repeat(title.len());
             
             if self.should_use_colors() {
-                println!("{}", title.cyan().bold());
-                println!("{}", separator.cyan());
+                writeln!(out, "{}", title.cyan().bold())?;
+                writeln!(out, "{}", separator.cyan())?;
             } else {
-                println!("{}", title);
-                println!("{}", separator);
+                writeln!(out, "{}", title)?;
+                writeln!(out, "{}", separator)?;
             }
-            println!();
+            writeln!(out)?;
         }
 
         for i in 0..max_lines {
@@ -64,26 +92,22 @@ impl<'a> DisplayManager<'a> {
             let info_line = info_lines.get(i).unwrap_or(&empty_string);
 
             let visual_width = self.calculate_visual_width(logo_line);
-            let padding_needed = if max_logo_width > visual_width {
-                max_logo_width - visual_width
-            } else {
-                0
-            };
+            let padding_needed = max_logo_width.saturating_sub(visual_width);
 
             if self.should_use_colors() {
-                print!("{}", logo_line.color(self.config.colors.logo.base.as_str()));
+                write!(out, "{}", logo_line.color(self.config.colors.logo.base.as_str()))?;
             } else {
-                print!("{}", logo_line);
+                write!(out, "{}", logo_line)?;
             }
 
-            print!("{}", " ".repeat(padding_needed));
-            print!("{}", " ".repeat(self.config.display.padding));
-            println!("{}", info_line);
+            write!(out, "{}", " ".repeat(padding_needed))?;
+            write!(out, "{}", " ".repeat(self.config.display.padding))?;
+            writeln!(out, "{}", info_line)?;
         }
 
         if !self.config.display.minimal {
-            println!();
-            self.display_color_bar(&info.colors, max_logo_width)?;
+            writeln!(out)?;
+            self.display_color_bar(&info.colors, max_logo_width, out)?;
         }
 
         Ok(())
@@ -122,7 +146,7 @@ impl<'a> DisplayManager<'a> {
             if ch == '\x1b' {
                 if chars.peek() == Some(&'[') {
                     chars.next();
-                    while let Some(next_ch) = chars.next() {
+                    for next_ch in chars.by_ref() {
                         if next_ch.is_ascii_alphabetic() {
                             break;
                         }
@@ -153,105 +177,49 @@ impl<'a> DisplayManager<'a> {
     }
 
     fn build_info_lines(&self, info: &SystemInfo) -> Vec<String> {
-        let mut lines = Vec::new();
-
-        if self.config.info.show_os && !info.os.is_empty() {
-            lines.push(self.format_info_line("OS", &info.os));
-        }
-
-        if self.config.info.show_kernel && !info.kernel.is_empty() {
-            lines.push(self.format_info_line("Kernel", &info.kernel));
-        }
-
-        if self.config.info.show_uptime && !info.uptime.is_empty() {
-            lines.push(self.format_info_line("Uptime", &info.uptime));
-        }
-
-        if self.config.info.show_packages && info.packages > 0 {
-            lines.push(self.format_info_line("Packages", &info.packages.to_string()));
-        }
-
-        if self.config.info.show_shell && !info.shell.is_empty() {
-            lines.push(self.format_info_line("Shell", &info.shell));
-        }
-
-        if self.config.info.show_resolution && !info.resolution.is_empty() && info.resolution != "unknown" {
-            lines.push(self.format_info_line("Resolution", &info.resolution));
-        }
-
-        if self.config.info.show_de && !info.desktop_environment.is_empty() && info.desktop_environment != "unknown" {
-            lines.push(self.format_info_line("DE", &info.desktop_environment));
-        }
-
-        if self.config.info.show_wm && !info.window_manager.is_empty() && info.window_manager != "unknown" {
-            lines.push(self.format_info_line("WM", &info.window_manager));
-        }
-
-        if self.config.info.show_terminal && !info.terminal.is_empty() && info.terminal != "unknown" {
-            lines.push(self.format_info_line("Terminal", &info.terminal));
-        }
-
-        if self.config.info.show_cpu && !info.cpu.is_empty() && info.cpu != "unknown" {
-            lines.push(self.format_info_line("CPU", &info.cpu));
-        }
-
-        if self.config.info.show_gpu && !info.gpu.is_empty() && info.gpu != "unknown" {
-            lines.push(self.format_info_line("GPU", &info.gpu));
-        }
-
-        if self.config.info.show_memory && info.memory.total > 0 {
-            let memory_str = format!(
-                "{} / {} ({}%)",
-                format_bytes(info.memory.used),
-                format_bytes(info.memory.total),
-                info.memory.percentage as u8
-            );
-            lines.push(self.format_info_line("Memory", &memory_str));
-        }
-
-        if self.config.info.show_disk && !info.disk.is_empty() {
-            for disk in &info.disk {
-                if disk.mount_point == "/" || disk.mount_point == "C:\\" {
-                    let disk_str = format!(
-                        "{} / {} ({}%)",
-                        format_bytes(disk.used),
-                        format_bytes(disk.total),
-                        disk.percentage as u8
-                    );
-                    lines.push(self.format_info_line("Disk", &disk_str));
-                    break;
-                }
+        let mut reports: Vec<&ModuleReport> = self.config.info.order.iter()
+            .filter_map(|id| info.modules.iter().find(|report| report.id() == id))
+            .collect();
+        for report in &info.modules {
+            if !reports.iter().any(|listed| listed.id() == report.id()) {
+                reports.push(report);
             }
         }
 
-        if self.config.info.show_battery {
-            if let Some(battery) = &info.battery {
-                let battery_str = format!("{}% ({})", battery.percentage, battery.status);
-                lines.push(self.format_info_line("Battery", &battery_str));
+        let mut lines = Vec::new();
+        for report in reports {
+            match &report.status {
+                ModuleStatus::Ready(value) => lines.extend(report.module.render(value, self)),
+                ModuleStatus::Unavailable => {}
+                ModuleStatus::Failed(reason) => lines.push(self.format_error_line(report.module.label(), reason)),
             }
         }
 
-        if self.config.info.show_locale && !info.locale.is_empty() && info.locale != "unknown" {
-            lines.push(self.format_info_line("Locale", &info.locale));
-        }
+        lines
+    }
 
-        if self.config.info.show_local_ip && !info.local_ip.is_empty() && info.local_ip != "unknown" {
-            lines.push(self.format_info_line("Local IP", &info.local_ip));
-        }
+    pub fn config(&self) -> &Config {
+        self.config
+    }
 
-        if self.config.info.show_users && !info.users.is_empty() {
-            let users_str = info.users.join(", ");
-            lines.push(self.format_info_line("Users", &users_str));
-        }
+    pub fn theme(&self) -> Option<&Theme> {
+        self.theme
+    }
 
-        if self.config.info.show_date {
-            lines.push(self.format_info_line("Date", &info.date));
-        }
+    pub fn format_info_line(&self, key: &str, value: &str) -> String {
+        self.format_info_line_raw(key, &self.colorize(value, &self.config.colors.value))
+    }
 
-        lines
+    fn format_error_line(&self, key: &str, reason: &str) -> String {
+        let color = if reason == TIMEOUT {
+            &self.config.colors.warning
+        } else {
+            &self.config.colors.error
+        };
+        self.format_info_line_raw(key, &self.colorize(reason, color))
     }
 
-    fn format_info_line(&self, key: &str, value: &str) -> String {
+    pub fn format_info_line_raw(&self, key: &str, value: &str) -> String {
         let separator = &self.config.display.separator;
         
         if self.should_use_colors() {
@@ -259,14 +227,22 @@ impl<'a> DisplayManager<'a> {
                 "{}{}{}",
                 key.color(self.config.colors.key.base.as_str()).bold(),
                 separator.color(self.config.colors.separator.base.as_str()),
-                value.color(self.config.colors.value.base.as_str())
+                value
             )
         } else {
             format!("{}{}{}", key, separator, value)
         }
     }
 
-    fn display_color_bar(&self, colors: &[String], logo_width: usize) -> Result<(), RFetchError> {
+    pub fn colorize(&self, text: &str, color: &AdvancedColor) -> String {
+        if self.should_use_colors() {
+            text.color(color.base.as_str()).to_string()
+        } else {
+            text.to_string()
+        }
+    }
+
+    fn display_color_bar(&self, colors: &[String], logo_width: usize, out: &mut Vec<u8>) -> Result<(), RFetchError> {
         if !self.should_use_colors() {
             return Ok(());
         }
@@ -277,23 +253,23 @@ impl<'a> DisplayManager<'a> {
         ];
 
         let padding = " ".repeat(logo_width + self.config.display.padding);
-        print!("{}", padding);
+        write!(out, "{}", padding)?;
 
         for (i, color_name) in color_names.iter().enumerate() {
             if i < colors.len() {
-                print!("{}", "██".color(*color_name));
+                write!(out, "{}", "██".color(*color_name))?;
             }
         }
-        println!();
+        writeln!(out)?;
 
-        print!("{}", padding);
+        write!(out, "{}", padding)?;
         for (i, color_name) in color_names.iter().enumerate() {
             if i < colors.len() {
                 let bright_color = format!("bright_{}", color_name);
-                print!("{}", "██".color(bright_color.as_str()));
+                write!(out, "{}", "██".color(bright_color.as_str()))?;
             }
         }
-        println!();
+        writeln!(out)?;
 
         Ok(())
     }
@@ -302,7 +278,7 @@ impl<'a> DisplayManager<'a> {
         match self.config.display.color_mode.as_str() {
             "always" => true,
             "never" => false,
-            "auto" => atty::is(atty::Stream::Stdout),
+            "auto" => self.terminal,
             _ => true,
         }
     }
@@ -342,4 +318,86 @@ impl ColorExt for String {
     fn color(&self, color_name: &str) -> ColoredString {
         self.as_str().color(color_name)
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::info::{MemoryInfo, UptimeInfo};
+    use crate::modules::{find, ModuleValue};
+    use std::path::Path;
+
+    fn report(id: &str, status: ModuleStatus) -> ModuleReport {
+        ModuleReport { module: find(id).unwrap().clone(), status, cached: None, timing: Default::default() }
+    }
+
+    fn ready<T: Send + Sync + 'static>(value: T) -> ModuleStatus {
+        ModuleStatus::Ready(Box::new(value) as ModuleValue)
+    }
+
+    fn sample_info() -> SystemInfo {
+        SystemInfo {
+            user: "alice".to_string(),
+            hostname: "wonderland".to_string(),
+            colors: vec!["■".to_string(); 8],
+            modules: vec![
+                report("os", ready("Arch Linux".to_string())),
+                report("kernel", ready("6.9.1-arch1-1".to_string())),
+                report("uptime", ready(UptimeInfo { seconds: 93784, boot_time: None, idle_seconds: None })),
+                report("packages", ready(1234u64)),
+                report("gpu", ModuleStatus::Failed(TIMEOUT.to_string())),
+                report("memory", ready(MemoryInfo {
+                    total: 16 * 1024 * 1024 * 1024,
+                    used: 6 * 1024 * 1024 * 1024,
+                    available: 10 * 1024 * 1024 * 1024,
+                    percentage: 37.5,
+                })),
+                report("battery", ModuleStatus::Unavailable),
+            ],
+        }
+    }
+
+    /// Compares against `tests/snapshots/<name>`; run with `UPDATE_SNAPSHOTS=1` to rewrite it.
+    fn assert_snapshot(name: &str, actual: &str) {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("snapshots").join(name);
+        if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+            std::fs::write(&path, actual).unwrap();
+            return;
+        }
+        let expected = std::fs::read_to_string(&path).unwrap_or_default();
+        assert_eq!(actual, expected, "snapshot {} differs", name);
+    }
+
+    fn render(config: &Config) -> String {
+        DisplayManager::new(config).with_terminal(false).render_to_string(&sample_info()).unwrap()
+    }
+
+    #[test]
+    fn test_snapshot_normal() {
+        let mut config = Config::default();
+        config.display.color_mode = "never".to_string();
+        assert_snapshot("normal.txt", &render(&config));
+    }
+
+    #[test]
+    fn test_snapshot_minimal() {
+        let mut config = Config::default();
+        config.display.color_mode = "never".to_string();
+        config.display.minimal = true;
+        config.apply_minimal();
+        assert_snapshot("minimal.txt", &render(&config));
+    }
+
+    #[test]
+    fn test_snapshot_json() {
+        let mut config = Config::default();
+        config.display.output_format = "json".to_string();
+        assert_snapshot("json.txt", &render(&config));
+    }
+
+    #[test]
+    fn test_auto_colors_follow_sink() {
+        let config = Config::default();
+        assert_eq!(config.display.color_mode, "auto");
+        assert!(!render(&config).contains('\x1b'));
+    }
+}
@@ -0,0 +1,504 @@
+//! `rfetch doctor`: checks the environment for reasons a field may be missing or look wrong, and
+//! says what to do about each problem found.
+//!
+//! The checks reuse the detectors' own probes (the helper programs they run, the files they read,
+//! the config and theme loaders), so a clean report means the fetch has what it needs.
+
+use colored::*;
+use std::fmt::Write as _;
+use std::fs;
+use std::io::{self, IsTerminal};
+use std::path::Path;
+
+use crate::config::Config;
+use crate::modules::{self, Origin};
+use crate::sysroot;
+use crate::tdl::{TdlParser, TdlValidator};
+use crate::themes;
+use crate::utils::{find_in_path, get_terminal, suggest};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    Ok,
+    Warn,
+    Fail,
+}
+
+#[derive(Debug, Clone)]
+pub struct Finding {
+    pub level: Level,
+    pub message: String,
+    pub hint: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Section {
+    pub title: &'static str,
+    pub findings: Vec<Finding>,
+}
+
+impl Finding {
+    fn ok<S: Into<String>>(message: S) -> Self {
+        Finding { level: Level::Ok, message: message.into(), hint: None }
+    }
+
+    fn warn<S: Into<String>, H: Into<String>>(message: S, hint: H) -> Self {
+        Finding { level: Level::Warn, message: message.into(), hint: Some(hint.into()) }
+    }
+
+    fn fail<S: Into<String>, H: Into<String>>(message: S, hint: H) -> Self {
+        Finding { level: Level::Fail, message: message.into(), hint: Some(hint.into()) }
+    }
+}
+
+/// Runs every check against the config at `config_path` (or the default one).
+pub fn run(config_path: Option<&String>) -> Vec<Section> {
+    let (configuration, config) = configuration(config_path);
+    let config = config.unwrap_or_default();
+
+    vec![
+        Section { title: "Helper programs", findings: helpers(&config) },
+        Section { title: "System files", findings: system_files() },
+        Section { title: "Terminal", findings: terminal() },
+        Section { title: "Locale", findings: locale() },
+        Section { title: "Configuration", findings: configuration },
+        Section { title: "Theme", findings: theme(&config) },
+    ]
+    .into_iter()
+    .filter(|section| !section.findings.is_empty())
+    .collect()
+}
+
+/// Only the configuration and theme checks, for `rfetch config check`.
+pub fn check_config(config_path: Option<&String>) -> Vec<Section> {
+    let (configuration, config) = configuration(config_path);
+    vec![
+        Section { title: "Configuration", findings: configuration },
+        Section { title: "Theme", findings: theme(&config.unwrap_or_default()) },
+    ]
+}
+
+pub fn count(sections: &[Section], level: Level) -> usize {
+    sections.iter().flat_map(|section| &section.findings).filter(|finding| finding.level == level).count()
+}
+
+pub fn render(sections: &[Section]) -> String {
+    let mut out = String::new();
+    for section in sections {
+        let _ = writeln!(out, "{}", section.title.bold());
+        for finding in &section.findings {
+            let tag = match finding.level {
+                Level::Ok => "[ok]  ".green(),
+                Level::Warn => "[warn]".yellow(),
+                Level::Fail => "[fail]".red().bold(),
+            };
+            let _ = writeln!(out, "  {} {}", tag, finding.message);
+            if let Some(hint) = &finding.hint {
+                let _ = writeln!(out, "         {} {}", "hint:".dimmed(), hint);
+            }
+        }
+        out.push('\n');
+    }
+
+    let (warnings, problems) = (count(sections, Level::Warn), count(sections, Level::Fail));
+    if warnings == 0 && problems == 0 {
+        let _ = writeln!(out, "No problems found.");
+    } else {
+        let _ = writeln!(out, "{} problem(s), {} warning(s).", problems, warnings);
+    }
+    out
+}
+
+fn module_enabled(config: &Config, id: &str) -> bool {
+    modules::MODULES
+        .iter()
+        .find(|module| module.id() == id)
+        .map(|module| config.info.is_enabled(module.as_ref()))
+        .unwrap_or(false)
+}
+
+#[cfg(target_os = "linux")]
+fn helpers(config: &Config) -> Vec<Finding> {
+    use crate::info::SystemInfo;
+    use crate::pci;
+    use crate::utils::{count_rpm_sqlite, is_termux, SPAWNED_PACKAGE_MANAGERS};
+
+    let mut findings = Vec::new();
+
+    if SystemInfo::get_drm_resolution().is_some() {
+        findings.push(Finding::ok("Resolution comes from /sys/class/drm"));
+    } else if sysroot::var("DISPLAY").is_ok() || sysroot::var("WAYLAND_DISPLAY").is_ok() {
+        findings.push(Finding::warn(
+            "/sys/class/drm reports no connected display: Resolution will be missing",
+            "enable kernel mode setting for your GPU driver, e.g. nvidia-drm.modeset=1 for NVIDIA",
+        ));
+    } else {
+        findings.push(Finding::ok("no display found: Resolution is unavailable, as expected when headless"));
+    }
+
+    match pci::PCI_IDS.iter().find(|path| sysroot::exists(path)) {
+        Some(path) => findings.push(Finding::ok(format!("lspci not needed: GPU is named from {}", path))),
+        None => findings.push(Finding::warn(
+            "pci.ids not found: GPU shows numeric ids such as Device 10de:2786",
+            "install hwdata (Arch, Fedora) or pci.ids (Debian/Ubuntu); pciutils pulls it in too",
+        )),
+    }
+
+    let rpm_sqlite = count_rpm_sqlite().is_some();
+    for (program, _, databases) in SPAWNED_PACKAGE_MANAGERS {
+        if *program == "rpm" && rpm_sqlite {
+            continue;
+        }
+        if databases.iter().any(sysroot::exists) && find_in_path(program).is_none() {
+            findings.push(Finding::warn(
+                format!("a {} database exists but {} is not in PATH: Packages undercounts", program, program),
+                format!("add the directory containing {} to PATH", program),
+            ));
+        }
+    }
+
+    if module_enabled(config, "sound") && find_in_path("wpctl").is_none() && find_in_path("pactl").is_none() {
+        findings.push(Finding::warn(
+            "neither wpctl nor pactl is installed: Sound cannot name the default output",
+            "install wireplumber (wpctl) or pulseaudio-utils (pactl)",
+        ));
+    }
+
+    if is_termux() && find_in_path("getprop").is_none() {
+        findings.push(Finding::warn(
+            "getprop not found: CPU and GPU fall back to /proc/cpuinfo",
+            "getprop ships with Android; make sure /system/bin is in PATH",
+        ));
+    }
+
+    findings
+}
+
+#[cfg(not(target_os = "linux"))]
+fn helpers(config: &Config) -> Vec<Finding> {
+    #[cfg(target_os = "macos")]
+    const HELPERS: &[(&str, &str, &str)] = &[
+        ("sw_vers", "os", "sw_vers is part of macOS; check that /usr/bin is in PATH"),
+        ("sysctl", "cpu", "sysctl is part of macOS; check that /usr/sbin is in PATH"),
+        ("system_profiler", "gpu", "system_profiler is part of macOS; check that /usr/sbin is in PATH"),
+        ("vm_stat", "memory", "vm_stat is part of macOS; check that /usr/bin is in PATH"),
+        ("df", "disk", "df is part of macOS; check that /bin is in PATH"),
+        ("pmset", "battery", "pmset is part of macOS; check that /usr/bin is in PATH"),
+    ];
+    #[cfg(windows)]
+    const HELPERS: &[(&str, &str, &str)] = &[(
+        "wmic",
+        "os",
+        "wmic is deprecated and missing from recent Windows 11 builds; add it under Settings > Optional features",
+    )];
+    #[cfg(not(any(target_os = "macos", windows)))]
+    const HELPERS: &[(&str, &str, &str)] = &[
+        ("ps", "de", "install procps so the shell, terminal and desktop can be found"),
+        ("df", "disk", "install a df implementation"),
+    ];
+
+    HELPERS
+        .iter()
+        .filter(|(_, module, _)| module_enabled(config, module))
+        .map(|(program, module, hint)| match find_in_path(program) {
+            Some(path) => Finding::ok(format!("{} found at {}", program, path.display())),
+            None => Finding::warn(format!("{} not found: the {} field may be missing", program, module), *hint),
+        })
+        .collect()
+}
+
+/// Files and directories detectors read, the field that depends on each, and whether a missing
+/// one is normal (no battery, no display, no systemd) rather than a sign of a hidden `/proc`.
+#[cfg(target_os = "linux")]
+const SYSTEM_PATHS: &[(&str, &str, bool)] = &[
+    ("/proc/sys/kernel/osrelease", "Kernel", false),
+    ("/proc/cpuinfo", "CPU", false),
+    ("/proc/meminfo", "Memory", false),
+    ("/proc/mounts", "Disk", false),
+    ("/proc/net/fib_trie", "Local IP", false),
+    ("/proc/net/route", "Local IP", false),
+    ("/sys/bus/pci/devices", "GPU", true),
+    ("/sys/class/drm", "Resolution", true),
+    ("/sys/class/power_supply", "Battery", true),
+    ("/sys/class/hwmon", "Sensors", true),
+    ("/run/systemd/sessions", "Users", true),
+];
+
+#[cfg(target_os = "linux")]
+fn system_files() -> Vec<Finding> {
+    SYSTEM_PATHS
+        .iter()
+        .map(|(path, field, optional)| {
+            let host = sysroot::path(path);
+            let readable = if host.is_dir() { fs::read_dir(&host).map(drop) } else { fs::File::open(&host).map(drop) };
+            match readable {
+                Ok(()) => Finding::ok(format!("{} ({})", path, field)),
+                Err(e) if e.kind() == io::ErrorKind::PermissionDenied => Finding::fail(
+                    format!("{} is not readable: {} will be missing", path, field),
+                    "check mount options such as hidepid=, or the sandbox rfetch runs in (Flatpak, snap, containers)",
+                ),
+                Err(_) if *optional => Finding::ok(format!("{} absent: {} is unavailable on this system", path, field)),
+                Err(_) => Finding::warn(
+                    format!("{} does not exist: {} will be missing", path, field),
+                    "mount /proc and /sys; containers and chroots often leave them out",
+                ),
+            }
+        })
+        .collect()
+}
+
+#[cfg(not(target_os = "linux"))]
+fn system_files() -> Vec<Finding> {
+    Vec::new()
+}
+
+fn terminal() -> Vec<Finding> {
+    let mut findings = Vec::new();
+    let var = |name: &str| sysroot::var(name).ok().filter(|value| !value.is_empty());
+
+    if !io::stdout().is_terminal() {
+        findings.push(Finding::ok("stdout is not a terminal: output is uncolored unless --color always"));
+    }
+    if var("NO_COLOR").is_some() {
+        findings.push(Finding::warn("NO_COLOR is set: output is uncolored", "unset NO_COLOR or pass --color always"));
+    }
+    if var("TERM").as_deref() == Some("dumb") {
+        findings.push(Finding::warn(
+            "TERM=dumb: the terminal claims no color or cursor support",
+            "set TERM to match your terminal, e.g. xterm-256color",
+        ));
+    }
+
+    match var("COLORTERM").as_deref() {
+        Some("truecolor") | Some("24bit") => findings.push(Finding::ok("24-bit color advertised by COLORTERM")),
+        _ => findings.push(Finding::ok("no 24-bit color advertised: themes use the 16 named colors, which is enough")),
+    }
+
+    let protocol = if var("KITTY_WINDOW_ID").is_some() || var("TERM").as_deref() == Some("xterm-kitty") {
+        Some("kitty graphics protocol")
+    } else {
+        match var("TERM_PROGRAM").as_deref() {
+            Some("iTerm.app") => Some("iTerm2 inline images"),
+            Some("WezTerm") => Some("iTerm2 inline images and kitty graphics (WezTerm)"),
+            _ => None,
+        }
+    };
+    findings.push(Finding::ok(match protocol {
+        Some(protocol) => format!("inline images: {}", protocol),
+        None => "no inline image protocol detected; logos are text and do not need one".to_string(),
+    }));
+
+    match get_terminal().as_str() {
+        "unknown" => findings.push(Finding::warn(
+            "the terminal could not be identified",
+            "run `RFETCH_LOG=terminal rfetch` to see which processes and variables were consulted",
+        )),
+        terminal => findings.push(Finding::ok(format!("terminal identified as {}", terminal))),
+    }
+
+    findings
+}
+
+#[cfg(not(windows))]
+fn locale() -> Vec<Finding> {
+    let setting = ["LC_ALL", "LC_CTYPE", "LANG"]
+        .iter()
+        .find_map(|name| sysroot::var(name).ok().filter(|value| !value.is_empty()).map(|value| (*name, value)));
+
+    let hint = "logos, separators and the color bar use characters such as ─ and █; \
+                set a UTF-8 locale (e.g. export LANG=en_US.UTF-8) or use --logo none";
+    vec![match setting {
+        Some((name, value)) if value.to_lowercase().replace('-', "").contains("utf8") => {
+            Finding::ok(format!("{}={} is UTF-8", name, value))
+        }
+        Some((name, value)) => Finding::warn(format!("{}={} is not a UTF-8 locale", name, value), hint),
+        None => Finding::warn("no locale set (LC_ALL, LC_CTYPE and LANG are empty): the C locale is ASCII only", hint),
+    }]
+}
+
+#[cfg(windows)]
+fn locale() -> Vec<Finding> {
+    Vec::new()
+}
+
+fn configuration(config_path: Option<&String>) -> (Vec<Finding>, Option<Config>) {
+    let path = match config_path.cloned().map(Ok).unwrap_or_else(Config::default_config_path) {
+        Ok(path) => path,
+        Err(e) => return (vec![Finding::warn(e.to_string(), "pass a config file with --config")], None),
+    };
+
+    if !Path::new(&path).exists() {
+        let finding = if config_path.is_some() {
+            Finding::fail(format!("{} does not exist", path), "check the path given to --config")
+        } else {
+            Finding::ok(format!("no config file at {}: using defaults", path))
+        };
+        return (vec![finding], None);
+    }
+
+    let content = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) => return (vec![Finding::fail(format!("{} cannot be read: {}", path, e), "check its permissions")], None),
+    };
+
+    let table: toml::Value = match content.parse() {
+        Ok(table) => table,
+        Err(e) => {
+            let message = format!("{} is not valid TOML{}: {}", path, line_of(&content, e.span()), e.message());
+            return (vec![Finding::fail(message, "fix the syntax; until then rfetch refuses to start")], None);
+        }
+    };
+
+    let config = toml::from_str::<Config>(&content);
+    let mut findings = match &config {
+        Ok(_) => vec![Finding::ok(format!("{} parses", path))],
+        Err(e) => vec![Finding::fail(
+            format!("{} does not load{}: {}", path, line_of(&content, e.span()), e.message()),
+            "fix the reported key; until then rfetch refuses to start",
+        )],
+    };
+
+    let registry = modules::registry(config.as_ref().unwrap_or(&Config::default()));
+    let ids: Vec<String> = registry.iter().map(|module| module.id().to_string()).collect();
+    for (key, suggestion) in unknown_keys(&table, &ids) {
+        let hint = match suggestion {
+            Some(suggestion) => format!("did you mean `{}`?", suggestion),
+            None => "remove it; unknown keys are ignored".to_string(),
+        };
+        findings.push(Finding::warn(format!("unknown key `{}` is ignored", key), hint));
+    }
+    for shadowed in modules::shadowed(config.as_ref().unwrap_or(&Config::default())) {
+        let hint = match shadowed.origin {
+            Origin::Plugin => format!("rename the `{}` plugin executable", shadowed.id),
+            _ => format!("give the [[custom]] module `{}` another id", shadowed.id),
+        };
+        findings.push(Finding::warn(shadowed.to_string(), hint));
+    }
+
+    // A config that fails to load can still name a theme worth checking.
+    let config = config.ok().or_else(|| {
+        let theme = table.get("display")?.get("theme")?.as_str()?.to_string();
+        let mut config = Config::default();
+        config.display.theme = Some(theme);
+        Some(config)
+    });
+    (findings, config)
+}
+
+fn line_of(content: &str, span: Option<std::ops::Range<usize>>) -> String {
+    match span {
+        Some(span) => format!(" at line {}", content[..span.start.min(content.len())].lines().count().max(1)),
+        None => String::new(),
+    }
+}
+
+/// Keys that deserialize fine but mean nothing: typos of section or field names, and `show_*`
+/// toggles for modules that do not exist. Each comes with the closest known name, if any.
+pub fn unknown_keys(file: &toml::Value, module_ids: &[String]) -> Vec<(String, Option<String>)> {
+    // Optional fields are left out when the defaults are serialized.
+    const OPTIONAL: &[(&str, &str)] = &[("display", "theme"), ("info", "order"), ("plugins", "dir")];
+
+    let known = match toml::Value::try_from(Config::default()) {
+        Ok(toml::Value::Table(known)) => known,
+        _ => return Vec::new(),
+    };
+    let file = match file.as_table() {
+        Some(file) => file,
+        None => return Vec::new(),
+    };
+
+    let mut unknown = Vec::new();
+    for (section, value) in file {
+        let known_section = match known.get(section) {
+            Some(known_section) => known_section,
+            None if section == "custom" => continue,
+            None => {
+                let suggestion = suggest(section, known.keys().map(String::as_str));
+                unknown.push((section.clone(), suggestion.map(str::to_string)));
+                continue;
+            }
+        };
+        let (keys, known_keys) = match (value.as_table(), known_section.as_table()) {
+            (Some(keys), Some(known_keys)) => (keys, known_keys),
+            _ => continue,
+        };
+
+        for key in keys.keys() {
+            if section == "info" {
+                if let Some(id) = key.strip_prefix("show_") {
+                    if !module_ids.iter().any(|module| module == id) {
+                        let suggestion = suggest(id, module_ids.iter().map(String::as_str));
+                        unknown.push((format!("info.{}", key), suggestion.map(|id| format!("show_{}", id))));
+                    }
+                    continue;
+                }
+            }
+            if known_keys.contains_key(key) || OPTIONAL.contains(&(section.as_str(), key.as_str())) {
+                continue;
+            }
+            let suggestion = suggest(key, known_keys.keys().map(String::as_str));
+            unknown.push((format!("{}.{}", section, key), suggestion.map(str::to_string)));
+        }
+    }
+    unknown
+}
+
+fn theme(config: &Config) -> Vec<Finding> {
+    let name = match &config.display.theme {
+        Some(name) => name,
+        None => return vec![Finding::ok("no theme configured: using the config's colors")],
+    };
+
+    if themes::load_theme(name).is_some() {
+        return vec![Finding::ok(format!("built-in theme `{}`", name))];
+    }
+
+    if !Path::new(name).exists() {
+        let available = themes::list_themes();
+        let hint = match suggest(name, available.iter().map(String::as_str)) {
+            Some(suggestion) => format!("did you mean `{}`?", suggestion),
+            None => format!("use one of {} or a path to a TDL file", available.join(", ")),
+        };
+        return vec![Finding::fail(format!("theme `{}` is neither built in nor a file", name), hint)];
+    }
+
+    match TdlParser::parse_file(name) {
+        Err(e) => vec![Finding::fail(
+            format!("{} does not parse: {}", name, e),
+            "compare it with `rfetch --generate-template toml`",
+        )],
+        Ok(tdl) => match TdlValidator::validate(&tdl) {
+            Ok(()) => vec![Finding::ok(format!("{} is a valid TDL theme", name))],
+            Err(errors) => errors
+                .into_iter()
+                .map(|error| Finding::warn(format!("{}: {}", name, error), "the theme still loads, but fix this in the file"))
+                .collect(),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unknown_keys() {
+        let file: toml::Value = toml::from_str(
+            "[display]\nlogo_typ = \"small\"\ntheme = \"neon\"\n\
+             [info]\nshow_os = true\nshow_kernal = false\norder = []\n\
+             [cahce]\nenabled = false\n\
+             [timeouts.modules]\npackages = 100\n",
+        )
+        .unwrap();
+        let ids = vec!["os".to_string(), "kernel".to_string()];
+
+        assert_eq!(
+            unknown_keys(&file, &ids),
+            vec![
+                ("cahce".to_string(), Some("cache".to_string())),
+                ("display.logo_typ".to_string(), Some("logo_type".to_string())),
+                ("info.show_kernal".to_string(), Some("show_kernel".to_string())),
+            ]
+        );
+    }
+}
@@ -11,6 +11,9 @@ pub enum RFetchError {
     #[error("Display error: {0}")]
     Display(String),
 
+    #[error("Theme error: {0}")]
+    Theme(String),
+
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 
@@ -45,6 +48,10 @@ impl RFetchError {
         RFetchError::Display(msg.into())
     }
 
+    pub fn theme<T: Into<String>>(msg: T) -> Self {
+        RFetchError::Theme(msg.into())
+    }
+
     pub fn unknown<T: Into<String>>(msg: T) -> Self {
         RFetchError::Unknown(msg.into())
     }
@@ -1,44 +1,162 @@
-use chrono::{DateTime, Local};
+use chrono::{DateTime, Local, TimeZone};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::env;
-use std::fs;
-use std::process::Command;
+use std::collections::{BTreeMap, VecDeque};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant};
 
+#[cfg(target_os = "windows")]
+use chrono::NaiveDateTime;
+
+use crate::cache;
 use crate::config::Config;
+use crate::desktop::{self, DesktopInfo};
 use crate::error::RFetchError;
+use crate::modules::{self, ModuleHandle, ModuleValue};
+#[cfg(target_os = "linux")]
+use crate::pci;
+use crate::process;
+use crate::sysroot;
+use crate::timings::{self, Commands, ModuleTiming};
+use crate::trace::{self, trace};
 use crate::utils::*;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SystemInfo {
     pub user: String,
     pub hostname: String,
-    pub os: String,
-    pub kernel: String,
-    pub uptime: String,
-    pub packages: u64,
-    pub shell: String,
-    pub resolution: String,
-    pub desktop_environment: String,
-    pub window_manager: String,
-    pub theme: String,
-    pub icons: String,
-    pub font: String,
-    pub cursor: String,
-    pub terminal: String,
-    pub cpu: String,
-    pub gpu: String,
-    pub memory: MemoryInfo,
-    pub disk: Vec<DiskInfo>,
-    pub battery: Option<BatteryInfo>,
-    pub locale: String,
-    pub local_ip: String,
-    pub public_ip: String,
-    pub users: Vec<String>,
-    pub date: String,
     pub colors: Vec<String>,
+    pub modules: Vec<ModuleReport>,
+}
+
+pub struct ModuleReport {
+    pub module: ModuleHandle,
+    pub status: ModuleStatus,
+    /// Set when the value came from the cache instead of being collected this run.
+    pub cached: Option<cache::Hit>,
+    pub timing: ModuleTiming,
+}
+
+pub enum ModuleStatus {
+    Ready(ModuleValue),
+    Unavailable,
+    Failed(String),
+}
+
+/// Version of the `--json` layout, bumped on incompatible changes. Output without the field is
+/// version 1: one fixed key per field, every value a string.
+pub const JSON_SCHEMA_VERSION: u32 = 2;
+
+pub const TIMEOUT: &str = "timeout";
+pub const PANIC: &str = "panic";
+
+// A panicking module is reported as failed only if the panic unwinds; with `panic = "abort"` it
+// would take the whole process down.
+#[cfg(not(panic = "unwind"))]
+compile_error!("rfetch isolates module panics with catch_unwind and must be built with panic = \"unwind\"");
+
+/// Upper bound on gather workers; modules mostly wait on files and subprocesses.
+const MAX_WORKERS: usize = 8;
+
+/// A module's result, with errors already rendered so a panic can be reported like one.
+type Outcome = Result<Option<ModuleValue>, String>;
+
+enum Event {
+    Started(usize, Instant),
+    Finished(usize, Outcome, Option<cache::Hit>, Duration),
+}
+
+/// What gather workers share: the modules to run, a queue of their indices and where to report.
+struct Pool {
+    config: Arc<Config>,
+    modules: Arc<Vec<ModuleHandle>>,
+    queue: Arc<Mutex<VecDeque<usize>>>,
+    commands: Arc<Vec<Commands>>,
+    timeouts: Arc<Vec<Duration>>,
+    desktop: Arc<OnceLock<DesktopInfo>>,
+    sender: mpsc::Sender<Event>,
+}
+
+impl Pool {
+    /// Starts a worker that runs queued modules until the queue is empty.
+    fn spawn_worker(&self) {
+        let config = Arc::clone(&self.config);
+        let modules = Arc::clone(&self.modules);
+        let queue = Arc::clone(&self.queue);
+        let commands = Arc::clone(&self.commands);
+        let timeouts = Arc::clone(&self.timeouts);
+        let desktop = Arc::clone(&self.desktop);
+        let sender = self.sender.clone();
+        thread::spawn(move || {
+            let _sysroot = sysroot::enter(config.sysroot.clone());
+            let _desktop = desktop::share(desktop);
+            loop {
+                let next = queue.lock().map(|mut queue| queue.pop_front()).unwrap_or(None);
+                let index = match next {
+                    Some(index) => index,
+                    None => break,
+                };
+                let module = &modules[index];
+                let started = Instant::now();
+                if sender.send(Event::Started(index, started)).is_err() {
+                    break;
+                }
+                let _deadline = sysroot::deadline(started + timeouts[index]);
+                let _timings = timings::record_into(Arc::clone(&commands[index]));
+                let _trace = trace::module(module.id());
+                let (outcome, hit) = match panic::catch_unwind(AssertUnwindSafe(|| cache::collect(module.as_ref(), &config))) {
+                    Ok((outcome, hit)) => (outcome.map_err(|e| e.to_string()), hit),
+                    Err(_) => (Err(PANIC.to_string()), None),
+                };
+                match &outcome {
+                    Ok(Some(_)) => trace!("ready"),
+                    Ok(None) => trace!("unavailable"),
+                    Err(e) => trace!("failed: {}", e),
+                }
+                if sender.send(Event::Finished(index, outcome, hit, started.elapsed())).is_err() {
+                    break;
+                }
+            }
+        });
+    }
 }
 
+/// `(implementer, part, name)` for ARM cores, as listed by util-linux `lscpu`.
+const ARM_CORES: &[(u32, u32, &str)] = &[
+    (0x41, 0xd03, "Cortex-A53"),
+    (0x41, 0xd04, "Cortex-A35"),
+    (0x41, 0xd05, "Cortex-A55"),
+    (0x41, 0xd07, "Cortex-A57"),
+    (0x41, 0xd08, "Cortex-A72"),
+    (0x41, 0xd09, "Cortex-A73"),
+    (0x41, 0xd0a, "Cortex-A75"),
+    (0x41, 0xd0b, "Cortex-A76"),
+    (0x41, 0xd0c, "Neoverse-N1"),
+    (0x41, 0xd0d, "Cortex-A77"),
+    (0x41, 0xd40, "Neoverse-V1"),
+    (0x41, 0xd41, "Cortex-A78"),
+    (0x41, 0xd44, "Cortex-X1"),
+    (0x41, 0xd46, "Cortex-A510"),
+    (0x41, 0xd47, "Cortex-A710"),
+    (0x41, 0xd48, "Cortex-X2"),
+    (0x41, 0xd49, "Neoverse-N2"),
+    (0x41, 0xd4d, "Cortex-A715"),
+    (0x41, 0xd4e, "Cortex-X3"),
+    (0x41, 0xd4f, "Neoverse-V2"),
+    (0x41, 0xd80, "Cortex-A520"),
+    (0x41, 0xd81, "Cortex-A720"),
+    (0x41, 0xd82, "Cortex-X4"),
+    (0x51, 0x800, "Kryo 2XX Gold"),
+    (0x51, 0x801, "Kryo 2XX Silver"),
+    (0x51, 0x802, "Kryo 3XX Gold"),
+    (0x51, 0x803, "Kryo 3XX Silver"),
+    (0x51, 0x804, "Kryo 4XX Gold"),
+    (0x51, 0x805, "Kryo 4XX Silver"),
+    (0x61, 0x022, "Icestorm"),
+    (0x61, 0x023, "Firestorm"),
+];
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MemoryInfo {
     pub total: u64,
@@ -59,144 +177,245 @@ pub struct DiskInfo {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct BatteryInfo {
-    pub percentage: u8,
-    pub status: String,
-    pub time_remaining: Option<String>,
+pub struct SoundInfo {
+    pub server: Option<String>,
+    pub server_version: Option<String>,
+    pub default_sink: Option<String>,
+    pub cards: Vec<String>,
 }
 
-impl SystemInfo {
-    pub fn gather(config: &Config) -> Result<Self, RFetchError> {
-        let mut info = SystemInfo {
-            user: get_username(),
-            hostname: get_hostname(),
-            os: String::new(),
-            kernel: String::new(),
-            uptime: String::new(),
-            packages: 0,
-            shell: String::new(),
-            resolution: String::new(),
-            desktop_environment: String::new(),
-            window_manager: String::new(),
-            theme: String::new(),
-            icons: String::new(),
-            font: String::new(),
-            cursor: String::new(),
-            terminal: String::new(),
-            cpu: String::new(),
-            gpu: String::new(),
-            memory: MemoryInfo {
-                total: 0,
-                used: 0,
-                available: 0,
-                percentage: 0.0,
-            },
-            disk: Vec::new(),
-            battery: None,
-            locale: String::new(),
-            local_ip: String::new(),
-            public_ip: String::new(),
-            users: Vec::new(),
-            date: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
-            colors: vec!["■".repeat(8)],
-        };
-
-        if config.info.show_os {
-            info.os = Self::get_os_info()?;
-        }
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UptimeInfo {
+    pub seconds: u64,
+    pub boot_time: Option<DateTime<Local>>,
+    pub idle_seconds: Option<u64>,
+}
 
-        if config.info.show_kernel {
-            info.kernel = Self::get_kernel_info()?;
+impl UptimeInfo {
+    pub fn from_seconds(seconds: u64, idle_seconds: Option<u64>) -> Self {
+        Self {
+            seconds,
+            boot_time: Local.timestamp_opt(Local::now().timestamp() - seconds as i64, 0).single(),
+            idle_seconds,
         }
+    }
 
-        if config.info.show_uptime {
-            info.uptime = Self::get_uptime()?;
+    pub fn format(&self, style: &str) -> String {
+        match style {
+            "short" => format_uptime(self.seconds),
+            "long" => format_uptime_long(self.seconds),
+            template => format_uptime_template(self.seconds, template),
         }
+    }
+}
 
-        if config.info.show_packages {
-            info.packages = count_packages();
-        }
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LocaleInfo {
+    pub lang: Option<String>,
+    pub lc_all: Option<String>,
+    pub overrides: BTreeMap<String, String>,
+}
 
-        if config.info.show_shell {
-            info.shell = get_shell();
-        }
+impl LocaleInfo {
+    pub fn effective(&self) -> Option<&str> {
+        self.lc_all.as_deref().or(self.lang.as_deref())
+    }
+}
 
-        if config.info.show_resolution {
-            info.resolution = Self::get_resolution()?;
-        }
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatteryInfo {
+    pub percentage: u8,
+    pub status: String,
+    pub time_remaining: Option<String>,
+}
 
-        if config.info.show_de {
-            info.desktop_environment = get_desktop_environment();
-        }
+impl ModuleReport {
+    pub fn id(&self) -> &str {
+        self.module.id()
+    }
+}
 
-        if config.info.show_wm {
-            info.window_manager = get_window_manager();
+impl SystemInfo {
+    pub fn get<T: 'static>(&self, id: &str) -> Option<&T> {
+        match &self.report(id)?.status {
+            ModuleStatus::Ready(value) => value.downcast_ref::<T>(),
+            _ => None,
         }
+    }
 
-        if config.info.show_terminal {
-            info.terminal = get_terminal();
-        }
+    pub fn errors(&self) -> BTreeMap<&str, &str> {
+        self.modules
+            .iter()
+            .filter_map(|report| match &report.status {
+                ModuleStatus::Failed(reason) => Some((report.id(), reason.as_str())),
+                _ => None,
+            })
+            .collect()
+    }
 
-        if config.info.show_cpu {
-            info.cpu = Self::get_cpu_info()?;
-        }
+    fn report(&self, id: &str) -> Option<&ModuleReport> {
+        self.modules.iter().find(|report| report.id() == id)
+    }
 
-        if config.info.show_gpu {
-            info.gpu = Self::get_gpu_info()?;
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut json = serde_json::Map::new();
+        json.insert("schema_version".to_string(), JSON_SCHEMA_VERSION.into());
+        json.insert("user".to_string(), self.user.clone().into());
+        json.insert("hostname".to_string(), self.hostname.clone().into());
+
+        let mut unavailable = Vec::new();
+        for report in &self.modules {
+            match &report.status {
+                ModuleStatus::Ready(value) => {
+                    json.insert(report.id().to_string(), report.module.to_json(value));
+                }
+                ModuleStatus::Unavailable => unavailable.push(report.id()),
+                ModuleStatus::Failed(_) => {}
+            }
         }
 
-        if config.info.show_memory {
-            info.memory = Self::get_memory_info()?;
+        json.insert("colors".to_string(), serde_json::json!(self.colors));
+        json.insert("errors".to_string(), serde_json::json!(self.errors()));
+        if !unavailable.is_empty() {
+            json.insert("unavailable".to_string(), serde_json::json!(unavailable));
         }
 
-        if config.info.show_disk {
-            info.disk = Self::get_disk_info()?;
-        }
+        serde_json::Value::Object(json)
+    }
 
-        if config.info.show_battery {
-            info.battery = Self::get_battery_info().ok();
-        }
+    pub fn gather(config: &Config) -> Self {
+        let enabled: Vec<ModuleHandle> = modules::registry(config)
+            .into_iter()
+            .filter(|module| config.info.is_enabled(module.as_ref()))
+            .collect();
+        Self::gather_modules(config, enabled)
+    }
 
-        if config.info.show_locale {
-            info.locale = Self::get_locale();
+    fn gather_modules(config: &Config, enabled: Vec<ModuleHandle>) -> Self {
+        let _sysroot = sysroot::enter(config.sysroot.clone());
+        let config = Arc::new(config.clone());
+        let enabled = Arc::new(enabled);
+        let queue = Arc::new(Mutex::new((0..enabled.len()).collect::<VecDeque<usize>>()));
+        let (sender, receiver) = mpsc::channel();
+        let commands: Vec<Commands> = enabled.iter().map(|_| Commands::default()).collect();
+        let timeouts: Vec<Duration> = enabled
+            .iter()
+            .map(|module| match config.timeouts.modules.get(module.id()) {
+                Some(ms) => Duration::from_millis(*ms),
+                None => module.timeout().unwrap_or_else(|| config.timeouts.for_module(module.id())),
+            })
+            .collect();
+        let pool = Pool {
+            config: Arc::clone(&config),
+            modules: Arc::clone(&enabled),
+            queue,
+            commands: Arc::new(commands.clone()),
+            timeouts: Arc::new(timeouts.clone()),
+            desktop: Arc::new(OnceLock::new()),
+            sender,
+        };
+        let workers = thread::available_parallelism().map(|n| n.get()).unwrap_or(4).clamp(4, MAX_WORKERS);
+        for _ in 0..workers.min(enabled.len()) {
+            pool.spawn_worker();
         }
 
-        if config.info.show_local_ip {
-            info.local_ip = Self::get_local_ip().unwrap_or_else(|_| "unknown".to_string());
+        let mut statuses: Vec<Option<ModuleStatus>> = enabled.iter().map(|_| None).collect();
+        let mut hits: Vec<Option<cache::Hit>> = enabled.iter().map(|_| None).collect();
+        let mut elapsed: Vec<Duration> = enabled.iter().map(|_| Duration::ZERO).collect();
+        // Modules a worker has started, with the instant they started.
+        let mut running: Vec<(usize, Instant)> = Vec::new();
+        let mut remaining = enabled.len();
+
+        // A module's deadline counts from when a worker picks it up. A worker that misses it is
+        // abandoned and replaced, so a module stuck outside a subprocess cannot hold up the
+        // queue; its helpers are killed at the same deadline (`sysroot::deadline`).
+        while remaining > 0 {
+            let next_deadline = running.iter().map(|(index, started)| *started + timeouts[*index]).min();
+            let message = match next_deadline {
+                Some(deadline) => receiver.recv_timeout(deadline.saturating_duration_since(Instant::now())),
+                None => receiver.recv().map_err(|_| RecvTimeoutError::Disconnected),
+            };
+            match message {
+                Ok(Event::Started(index, started)) => running.push((index, started)),
+                Ok(Event::Finished(index, outcome, hit, took)) => {
+                    // A late result from a module that was already reported as timed out is dropped.
+                    if statuses[index].is_some() {
+                        continue;
+                    }
+                    running.retain(|(running_index, _)| *running_index != index);
+                    remaining -= 1;
+                    hits[index] = hit;
+                    elapsed[index] = took;
+                    statuses[index] = Some(match outcome {
+                        Ok(Some(value)) => ModuleStatus::Ready(value),
+                        Ok(None) => ModuleStatus::Unavailable,
+                        Err(e) => ModuleStatus::Failed(e),
+                    });
+                }
+                Err(RecvTimeoutError::Timeout) => {
+                    let now = Instant::now();
+                    for (index, started) in running.iter().filter(|(index, started)| *started + timeouts[*index] <= now) {
+                        let _trace = trace::module(enabled[*index].id());
+                        trace!("timed out after {} ms", (now - *started).as_millis());
+                        timings::kill_running(&commands[*index]);
+                        statuses[*index] = Some(ModuleStatus::Failed(TIMEOUT.to_string()));
+                        elapsed[*index] = now - *started;
+                        remaining -= 1;
+                        pool.spawn_worker();
+                    }
+                    running.retain(|(index, _)| statuses[*index].is_none());
+                }
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
         }
 
-        if config.info.show_users {
-            info.users = Self::get_logged_users();
+        let enabled = Arc::try_unwrap(enabled).unwrap_or_else(|shared| (*shared).clone());
+        SystemInfo {
+            user: get_username(),
+            hostname: get_hostname(),
+            colors: Self::generate_color_bar(),
+            modules: enabled
+                .into_iter()
+                .zip(statuses.into_iter().zip(hits))
+                .zip(elapsed.into_iter().zip(commands))
+                .filter_map(|((module, (status, cached)), (elapsed, commands))| {
+                    let commands = commands.lock().map(|commands| commands.clone()).unwrap_or_default();
+                    Some(ModuleReport {
+                        module,
+                        status: status?,
+                        cached,
+                        timing: ModuleTiming { elapsed, commands },
+                    })
+                })
+                .collect(),
         }
-
-        info.colors = Self::generate_color_bar();
-
-        Ok(info)
     }
 
     #[cfg(target_os = "linux")]
-    fn get_os_info() -> Result<String, RFetchError> {
+    pub fn get_os_info() -> Result<String, RFetchError> {
         if Self::is_termux() {
-            if let Ok(version) = env::var("TERMUX_VERSION") {
+            if let Ok(version) = sysroot::var("TERMUX_VERSION") {
                 return Ok(format!("Termux {}", version));
             }
             return Ok("Termux".to_string());
         }
 
-        if let Ok(_) = fs::read_to_string("/etc/arch-release") {
-            if let Ok(content) = fs::read_to_string("/etc/os-release") {
+        if sysroot::read_to_string("/etc/arch-release").is_ok() {
+            if let Ok(content) = sysroot::read_to_string("/etc/os-release") {
                 for line in content.lines() {
                     if line.starts_with("PRETTY_NAME=") {
                         let name = line.split('=').nth(1).unwrap_or("")
                             .trim_matches('"');
+                        trace!("/etc/arch-release present, using PRETTY_NAME {:?}", name);
                         return Ok(name.to_string());
                     }
                 }
             }
+            trace!("/etc/arch-release present, no PRETTY_NAME in os-release");
             return Ok("Arch Linux".to_string());
         }
 
-        if let Ok(content) = fs::read_to_string("/etc/lsb-release") {
+        if let Ok(content) = sysroot::read_to_string("/etc/lsb-release") {
             let mut distrib_id = String::new();
             let mut distrib_release = String::new();
             
@@ -209,22 +428,26 @@ impl SystemInfo {
             }
             
             if !distrib_id.is_empty() && !distrib_release.is_empty() {
+                trace!("using DISTRIB_ID and DISTRIB_RELEASE from /etc/lsb-release");
                 return Ok(format!("{} {}", distrib_id, distrib_release));
             }
+            trace!("/etc/lsb-release has no DISTRIB_ID or DISTRIB_RELEASE");
         }
 
-        if let Ok(content) = fs::read_to_string("/etc/os-release") {
+        if let Ok(content) = sysroot::read_to_string("/etc/os-release") {
             for line in content.lines() {
                 if line.starts_with("PRETTY_NAME=") {
                     let name = line.split('=').nth(1).unwrap_or("")
                         .trim_matches('"');
+                    trace!("using PRETTY_NAME {:?} from /etc/os-release", name);
                     return Ok(name.to_string());
                 }
             }
         }
 
-        if let Ok(content) = fs::read_to_string("/etc/issue") {
+        if let Ok(content) = sysroot::read_to_string("/etc/issue") {
             let first_line = content.lines().next().unwrap_or("Linux");
+            trace!("using the first line of /etc/issue");
             return Ok(first_line.replace("\\n", "").replace("\\l", "").trim().to_string());
         }
 
@@ -232,10 +455,10 @@ impl SystemInfo {
     }
 
     #[cfg(target_os = "macos")]
-    fn get_os_info() -> Result<String, RFetchError> {
-        if let Ok(output) = Command::new("sw_vers").arg("-productName").output() {
+    pub fn get_os_info() -> Result<String, RFetchError> {
+        if let Ok(output) = sysroot::run("sw_vers", &["-productName"]) {
             let product = String::from_utf8_lossy(&output.stdout).trim().to_string();
-            if let Ok(version_output) = Command::new("sw_vers").arg("-productVersion").output() {
+            if let Ok(version_output) = sysroot::run("sw_vers", &["-productVersion"]) {
                 let version = String::from_utf8_lossy(&version_output.stdout).trim().to_string();
                 return Ok(format!("{} {}", product, version));
             }
@@ -245,17 +468,17 @@ impl SystemInfo {
     }
 
     #[cfg(target_os = "ios")]
-    fn get_os_info() -> Result<String, RFetchError> {
-        if let Ok(version) = env::var("IPHONEOS_DEPLOYMENT_TARGET") {
+    pub fn get_os_info() -> Result<String, RFetchError> {
+        if let Ok(version) = sysroot::var("IPHONEOS_DEPLOYMENT_TARGET") {
             return Ok(format!("iOS {}", version));
         }
         
-        if let Ok(output) = Command::new("sw_vers").arg("-productVersion").output() {
+        if let Ok(output) = sysroot::run("sw_vers", &["-productVersion"]) {
             let version = String::from_utf8_lossy(&output.stdout).trim().to_string();
             return Ok(format!("iOS {}", version));
         }
         
-        if let Ok(_) = fs::read_to_string("/proc/ish") {
+        if let Ok(_) = sysroot::read_to_string("/proc/ish") {
             return Ok("iOS (iSH)".to_string());
         }
         
@@ -263,11 +486,8 @@ impl SystemInfo {
     }
 
     #[cfg(target_os = "windows")]
-    fn get_os_info() -> Result<String, RFetchError> {
-        if let Ok(output) = Command::new("wmic")
-            .args(&["os", "get", "Caption", "/value"])
-            .output()
-        {
+    pub fn get_os_info() -> Result<String, RFetchError> {
+        if let Ok(output) = sysroot::run("wmic", &["os", "get", "Caption", "/value"]) {
             let output_str = String::from_utf8_lossy(&output.stdout);
             for line in output_str.lines() {
                 if line.starts_with("Caption=") {
@@ -279,32 +499,36 @@ impl SystemInfo {
     }
 
     #[cfg(unix)]
-    fn get_kernel_info() -> Result<String, RFetchError> {
+    pub fn get_kernel_info() -> Result<String, RFetchError> {
+        let release = sysroot::read_to_string("/proc/sys/kernel/osrelease")
+            .map(|release| release.trim().to_string())
+            .ok()
+            .filter(|release| !release.is_empty())
+            .or_else(|| {
+                // The syscall describes the running kernel, so it cannot answer for a sysroot.
+                trace!("no osrelease, falling back to uname(2)");
+                sysroot::is_live()
+                    .then(nix::sys::utsname::uname)
+                    .and_then(|uts| uts.ok())
+                    .map(|uts| uts.release().to_string_lossy().to_string())
+            });
+
         #[cfg(target_os = "ios")]
         {
             if Self::is_ios() {
-                if let Ok(output) = Command::new("uname").arg("-r").output() {
-                    let kernel = String::from_utf8_lossy(&output.stdout).trim().to_string();
-                    return Ok(format!("Darwin {}", kernel));
-                } else {
-                    return Ok("Darwin (iOS)".to_string());
-                }
+                return Ok(match release {
+                    Some(release) => format!("Darwin {}", release),
+                    None => "Darwin (iOS)".to_string(),
+                });
             }
         }
 
-        if let Ok(output) = Command::new("uname").arg("-r").output() {
-            Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
-        } else {
-            Ok("unknown".to_string())
-        }
+        Ok(release.unwrap_or_else(|| "unknown".to_string()))
     }
 
     #[cfg(windows)]
-    fn get_kernel_info() -> Result<String, RFetchError> {
-        if let Ok(output) = Command::new("wmic")
-            .args(&["os", "get", "Version", "/value"])
-            .output()
-        {
+    pub fn get_kernel_info() -> Result<String, RFetchError> {
+        if let Ok(output) = sysroot::run("wmic", &["os", "get", "Version", "/value"]) {
             let output_str = String::from_utf8_lossy(&output.stdout);
             for line in output_str.lines() {
                 if line.starts_with("Version=") {
@@ -316,91 +540,66 @@ impl SystemInfo {
     }
 
     #[cfg(target_os = "linux")]
-    fn get_uptime() -> Result<String, RFetchError> {
-        if let Ok(content) = fs::read_to_string("/proc/uptime") {
-            let uptime_seconds = content
-                .split_whitespace()
-                .next()
-                .and_then(|s| s.parse::<f64>().ok())
-                .unwrap_or(0.0) as u64;
-            Ok(format_uptime(uptime_seconds))
-        } else {
-            Ok("unknown".to_string())
-        }
-    }
+    pub fn get_uptime() -> Result<UptimeInfo, RFetchError> {
+        let content = sysroot::read_to_string("/proc/uptime")
+            .map_err(|e| RFetchError::system_info(format!("Could not read /proc/uptime: {}", e)))?;
+        let mut fields = content.split_whitespace().map(|s| s.parse::<f64>().ok());
 
-    #[cfg(target_os = "macos")]
-    fn get_uptime() -> Result<String, RFetchError> {
-        if let Ok(output) = Command::new("uptime").output() {
-            let uptime_str = String::from_utf8_lossy(&output.stdout);
-            if let Some(up_index) = uptime_str.find("up ") {
-                let after_up = &uptime_str[up_index + 3..];
-                if let Some(comma_index) = after_up.find(',') {
-                    let mut uptime_part = after_up[..comma_index].trim();
-                    if let Some(remaining) = after_up.get(comma_index + 1..) {
-                        if let Some(second_comma) = remaining.find(',') {
-                            uptime_part = &after_up[..comma_index + 1 + second_comma];
-                        }
-                    }
-                    return Ok(uptime_part.trim_end_matches(',').trim().to_string());
-                } else {
-                    let end_index = after_up.find(" user").unwrap_or(after_up.len());
-                    return Ok(after_up[..end_index].trim().to_string());
-                }
-            }
-            Ok("unknown".to_string())
-        } else {
-            Ok("unknown".to_string())
-        }
+        let seconds = fields.next().flatten().unwrap_or(0.0) as u64;
+        let idle_seconds = fields.next().flatten().map(|idle| idle as u64);
+
+        Ok(UptimeInfo::from_seconds(seconds, idle_seconds))
     }
 
-    #[cfg(target_os = "ios")]
-    fn get_uptime() -> Result<String, RFetchError> {
-        if Self::is_ios() {
-            if let Ok(output) = Command::new("uptime").output() {
-                let uptime_str = String::from_utf8_lossy(&output.stdout);
-                if let Some(up_index) = uptime_str.find("up ") {
-                    let after_up = &uptime_str[up_index + 3..];
-                    if let Some(comma_index) = after_up.find(',') {
-                        return Ok(after_up[..comma_index].trim().to_string());
-                    }
-                }
-            }
-            
-            if let Ok(content) = fs::read_to_string("/proc/uptime") {
-                let uptime_seconds = content
-                    .split_whitespace()
-                    .next()
-                    .and_then(|s| s.parse::<f64>().ok())
-                    .unwrap_or(0.0) as u64;
-                return Ok(format_uptime(uptime_seconds));
-            }
-        }
-        
-        Ok("unknown".to_string())
+    #[cfg(any(target_os = "macos", target_os = "ios"))]
+    pub fn get_uptime() -> Result<UptimeInfo, RFetchError> {
+        let output = sysroot::run("sysctl", &["-n", "kern.boottime"])
+            .map_err(|e| RFetchError::system_info(format!("Could not run sysctl: {}", e)))?;
+        let output_str = String::from_utf8_lossy(&output.stdout);
+
+        let boot_seconds = output_str
+            .split("sec =")
+            .nth(1)
+            .and_then(|rest| rest.split(',').next())
+            .and_then(|sec| sec.trim().parse::<i64>().ok())
+            .ok_or_else(|| RFetchError::system_info("Could not parse kern.boottime"))?;
+
+        let seconds = (Local::now().timestamp() - boot_seconds).max(0) as u64;
+        Ok(UptimeInfo::from_seconds(seconds, None))
     }
 
     #[cfg(target_os = "windows")]
-    fn get_uptime() -> Result<String, RFetchError> {
-        if let Ok(output) = Command::new("wmic")
-            .args(&["os", "get", "LastBootUpTime", "/value"])
-            .output()
-        {
+    pub fn get_uptime() -> Result<UptimeInfo, RFetchError> {
+        if let Ok(output) = sysroot::run("wmic", &["os", "get", "LastBootUpTime", "/value"]) {
             let output_str = String::from_utf8_lossy(&output.stdout);
             for line in output_str.lines() {
-                if line.starts_with("LastBootUpTime=") {
-                    let boot_time = line.replace("LastBootUpTime=", "").trim().to_string();
-                    return Ok(boot_time);
+                if let Some(boot_time) = line.strip_prefix("LastBootUpTime=") {
+                    if let Some(boot) = boot_time.get(..14)
+                        .and_then(|s| NaiveDateTime::parse_from_str(s, "%Y%m%d%H%M%S").ok())
+                        .and_then(|naive| Local.from_local_datetime(&naive).single())
+                    {
+                        let seconds = (Local::now() - boot).num_seconds().max(0) as u64;
+                        return Ok(UptimeInfo::from_seconds(seconds, None));
+                    }
                 }
             }
         }
-        Ok("unknown".to_string())
+        Err(RFetchError::system_info("Could not determine uptime"))
     }
 
-    fn get_resolution() -> Result<String, RFetchError> {
-        #[cfg(unix)]
+    pub fn get_resolution() -> Result<String, RFetchError> {
+        #[cfg(target_os = "linux")]
         {
-            if let Ok(output) = Command::new("xrandr").output() {
+            if let Some(resolution) = Self::get_drm_resolution() {
+                trace!("using the preferred mode of a connected DRM connector");
+                return Ok(resolution);
+            }
+        }
+
+        // The BSDs have no DRM sysfs to read modes from; ask the X server instead.
+        #[cfg(all(unix, not(any(target_os = "linux", target_os = "macos"))))]
+        if sysroot::var("DISPLAY").is_ok() {
+            if let Ok(output) = sysroot::run("xrandr", &[]) {
                 let output_str = String::from_utf8_lossy(&output.stdout);
                 for line in output_str.lines() {
                     if line.contains("*") && line.contains("x") {
@@ -414,10 +613,7 @@ impl SystemInfo {
 
         #[cfg(target_os = "macos")]
         {
-            if let Ok(output) = Command::new("system_profiler")
-                .arg("SPDisplaysDataType")
-                .output()
-            {
+            if let Ok(output) = sysroot::run("system_profiler", &["SPDisplaysDataType"]) {
                 let _output_str = String::from_utf8_lossy(&output.stdout);
                 return Ok("unknown".to_string());
             }
@@ -426,14 +622,14 @@ impl SystemInfo {
         Ok("unknown".to_string())
     }
 
-    fn get_cpu_info() -> Result<String, RFetchError> {
+    pub fn get_cpu_info() -> Result<String, RFetchError> {
         #[cfg(target_os = "linux")]
         {
             if Self::is_termux() {
-                if let Ok(output) = Command::new("getprop").arg("ro.product.cpu.abi").output() {
+                if let Ok(output) = sysroot::run("getprop", &["ro.product.cpu.abi"]) {
                     let cpu_abi = String::from_utf8_lossy(&output.stdout).trim().to_string();
                     if !cpu_abi.is_empty() {
-                        if let Ok(model_output) = Command::new("getprop").arg("ro.product.model").output() {
+                        if let Ok(model_output) = sysroot::run("getprop", &["ro.product.model"]) {
                             let model = String::from_utf8_lossy(&model_output.stdout).trim().to_string();
                             if !model.is_empty() {
                                 return Ok(format!("{} ({})", model, cpu_abi));
@@ -443,47 +639,30 @@ impl SystemInfo {
                     }
                 }
                 
-                if let Ok(output) = Command::new("lscpu").output() {
-                    let output_str = String::from_utf8_lossy(&output.stdout);
-                    for line in output_str.lines() {
-                        if line.starts_with("Model name:") {
-                            if let Some(name) = line.split(':').nth(1) {
-                                return Ok(name.trim().to_string());
-                            }
-                        }
-                    }
-                }
-                
-                return Ok("ARM".to_string());
+                return Ok(sysroot::read_to_string("/proc/cpuinfo")
+                    .ok()
+                    .and_then(|content| Self::parse_cpu_model(&content))
+                    .unwrap_or_else(|| "ARM".to_string()));
             }
 
-            if let Ok(content) = fs::read_to_string("/proc/cpuinfo") {
-                for line in content.lines() {
-                    if line.starts_with("model name") {
-                        if let Some(name) = line.split(':').nth(1) {
-                            return Ok(name.trim().to_string());
-                        }
-                    }
-                }
+            if let Some(model) = sysroot::read_to_string("/proc/cpuinfo")
+                .ok()
+                .and_then(|content| Self::parse_cpu_model(&content))
+            {
+                return Ok(model);
             }
         }
 
         #[cfg(target_os = "macos")]
         {
-            if let Ok(output) = Command::new("sysctl")
-                .args(&["-n", "machdep.cpu.brand_string"])
-                .output()
-            {
+            if let Ok(output) = sysroot::run("sysctl", &["-n", "machdep.cpu.brand_string"]) {
                 return Ok(String::from_utf8_lossy(&output.stdout).trim().to_string());
             }
         }
 
         #[cfg(target_os = "windows")]
         {
-            if let Ok(output) = Command::new("wmic")
-                .args(&["cpu", "get", "name", "/value"])
-                .output()
-            {
+            if let Ok(output) = sysroot::run("wmic", &["cpu", "get", "name", "/value"]) {
                 let output_str = String::from_utf8_lossy(&output.stdout);
                 for line in output_str.lines() {
                     if line.starts_with("Name=") {
@@ -496,23 +675,111 @@ impl SystemInfo {
         Ok("unknown".to_string())
     }
 
+    /// The preferred mode of the first connected display, e.g. `card1-eDP-1/modes`.
+    #[cfg(target_os = "linux")]
+    pub(crate) fn get_drm_resolution() -> Option<String> {
+        let mut connectors: Vec<_> = sysroot::read_dir("/sys/class/drm")
+            .ok()?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.file_name().is_some_and(|name| name.to_string_lossy().contains('-')))
+            .collect();
+        connectors.sort();
+
+        connectors.iter().find_map(|connector| {
+            let status = std::fs::read_to_string(connector.join("status")).ok()?;
+            if status.trim() != "connected" {
+                return None;
+            }
+            let modes = std::fs::read_to_string(connector.join("modes")).ok()?;
+            modes.lines().next().map(|mode| mode.trim().to_string()).filter(|mode| !mode.is_empty())
+        })
+    }
+
+    /// Device and filesystem type of the last filesystem mounted on `/` in `/proc/mounts`.
+    pub fn parse_root_mount(mounts: &str) -> Option<(String, String)> {
+        mounts.lines().rev().find_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            match fields[..] {
+                [device, "/", filesystem, ..] if filesystem != "rootfs" => {
+                    Some((device.to_string(), filesystem.to_string()))
+                }
+                _ => None,
+            }
+        })
+    }
+
+    /// Reads the `model name` line, or names ARM cores from their implementer and part numbers
+    /// the way `lscpu` does, since ARM kernels print no model name.
+    pub fn parse_cpu_model(cpuinfo: &str) -> Option<String> {
+        let field = |line: &str, key: &str| {
+            let (name, value) = line.split_once(':')?;
+            (name.trim() == key).then(|| value.trim().to_string())
+        };
+
+        if let Some(model) = cpuinfo.lines().find_map(|line| field(line, "model name")) {
+            trace!("using the model name line");
+            return Some(model);
+        }
+
+        let mut cores: Vec<&str> = Vec::new();
+        let mut implementer = None;
+        for line in cpuinfo.lines() {
+            if let Some(value) = field(line, "CPU implementer") {
+                implementer = u32::from_str_radix(value.trim_start_matches("0x"), 16).ok();
+            } else if let Some(value) = field(line, "CPU part") {
+                let part = u32::from_str_radix(value.trim_start_matches("0x"), 16).ok();
+                let name = ARM_CORES
+                    .iter()
+                    .find(|(core_implementer, core_part, _)| Some(*core_implementer) == implementer && Some(*core_part) == part)
+                    .map(|(_, _, name)| *name);
+                if let Some(name) = name.filter(|name| !cores.contains(name)) {
+                    cores.push(name);
+                }
+            }
+        }
+
+        if !cores.is_empty() {
+            trace!("no model name, named {} core type(s) from CPU implementer and part", cores.len());
+            return Some(cores.join(" + "));
+        }
+        trace!("no model name or known ARM cores, trying the Hardware line");
+        cpuinfo.lines().find_map(|line| field(line, "Hardware"))
+    }
+
     fn is_termux() -> bool {
-        env::var("TERMUX_VERSION").is_ok() ||
-        env::var("PREFIX").map(|p| p.contains("com.termux")).unwrap_or(false) ||
-        std::path::Path::new("/data/data/com.termux").exists()
+        sysroot::var("TERMUX_VERSION").is_ok() ||
+        sysroot::var("PREFIX").map(|p| p.contains("com.termux")).unwrap_or(false) ||
+        sysroot::exists("/data/data/com.termux")
     }
 
+    #[cfg(target_os = "ios")]
     fn is_ios() -> bool {
-        env::var("IPHONEOS_DEPLOYMENT_TARGET").is_ok() ||
-        std::path::Path::new("/proc/ish").exists() ||
-        env::var("SIMULATOR_DEVICE_NAME").is_ok() ||
+        sysroot::var("IPHONEOS_DEPLOYMENT_TARGET").is_ok() ||
+        sysroot::exists("/proc/ish") ||
+        sysroot::var("SIMULATOR_DEVICE_NAME").is_ok() ||
         std::path::Path::new("/Applications").exists() && std::path::Path::new("/System/Library/CoreServices/SpringBoard.app").exists()
     }
 
-    fn get_gpu_info() -> Result<String, RFetchError> {
+    pub fn get_gpu_info() -> Result<String, RFetchError> {
+        // The BSDs have no DRM sysfs to read modes from; ask the X server instead.
+        #[cfg(all(unix, not(any(target_os = "linux", target_os = "macos"))))]
+        if sysroot::var("DISPLAY").is_ok() {
+            if let Ok(output) = sysroot::run("xrandr", &[]) {
+                let output_str = String::from_utf8_lossy(&output.stdout);
+                for line in output_str.lines() {
+                    if line.contains("*") && line.contains("x") {
+                        if let Some(resolution) = line.split_whitespace().next() {
+                            return Ok(resolution.to_string());
+                        }
+                    }
+                }
+            }
+        }
+
         #[cfg(target_os = "macos")]
         {
-            if let Ok(output) = Command::new("system_profiler").arg("SPDisplaysDataType").output() {
+            if let Ok(output) = sysroot::run("system_profiler", &["SPDisplaysDataType"]) {
                 let output_str = String::from_utf8_lossy(&output.stdout);
                 for line in output_str.lines() {
                     if line.trim().starts_with("Chipset Model:") {
@@ -535,21 +802,21 @@ impl SystemInfo {
         #[cfg(target_os = "linux")]
         {
             if Self::is_termux() {
-                if let Ok(output) = Command::new("getprop").arg("ro.hardware.vulkan").output() {
+                if let Ok(output) = sysroot::run("getprop", &["ro.hardware.vulkan"]) {
                     let vulkan = String::from_utf8_lossy(&output.stdout).trim().to_string();
                     if !vulkan.is_empty() && vulkan != "0" {
                         return Ok(format!("Vulkan: {}", vulkan));
                     }
                 }
                 
-                if let Ok(output) = Command::new("getprop").arg("ro.hardware.egl").output() {
+                if let Ok(output) = sysroot::run("getprop", &["ro.hardware.egl"]) {
                     let egl = String::from_utf8_lossy(&output.stdout).trim().to_string();
                     if !egl.is_empty() {
                         return Ok(format!("EGL: {}", egl));
                     }
                 }
                 
-                if let Ok(output) = Command::new("getprop").arg("debug.egl.hw").output() {
+                if let Ok(output) = sysroot::run("getprop", &["debug.egl.hw"]) {
                     let hw = String::from_utf8_lossy(&output.stdout).trim().to_string();
                     if !hw.is_empty() && hw != "0" {
                         return Ok("Hardware Accelerated".to_string());
@@ -559,24 +826,20 @@ impl SystemInfo {
                 return Ok("Integrated".to_string());
             }
 
-            if let Ok(output) = Command::new("lspci").output() {
-                let output_str = String::from_utf8_lossy(&output.stdout);
-                for line in output_str.lines() {
-                    if line.contains("VGA") || line.contains("3D") {
-                        if let Some(gpu) = line.split(':').nth(2) {
-                            return Ok(gpu.trim().to_string());
-                        }
-                    }
-                }
+            let devices = pci::devices();
+            if let Some(gpu) = devices
+                .iter()
+                .find(|device| device.class == pci::VGA_CONTROLLER || device.class == pci::CONTROLLER_3D)
+            {
+                trace!("using PCI {} [{:04x}:{:04x}], class {:04x}", gpu.slot, gpu.vendor, gpu.device, gpu.class);
+                return Ok(gpu.describe());
             }
+            trace!("no display controller among {} PCI devices", devices.len());
         }
 
         #[cfg(target_os = "windows")]
         {
-            if let Ok(output) = Command::new("wmic")
-                .args(&["path", "win32_VideoController", "get", "name", "/value"])
-                .output()
-            {
+            if let Ok(output) = sysroot::run("wmic", &["path", "win32_VideoController", "get", "name", "/value"]) {
                 let output_str = String::from_utf8_lossy(&output.stdout);
                 for line in output_str.lines() {
                     if line.starts_with("Name=") && !line.trim_end().ends_with("=") {
@@ -588,7 +851,7 @@ impl SystemInfo {
 
         #[cfg(target_os = "ios")]
         {
-            if let Ok(output) = Command::new("system_profiler").arg("SPDisplaysDataType").output() {
+            if let Ok(output) = sysroot::run("system_profiler", &["SPDisplaysDataType"]) {
                 let output_str = String::from_utf8_lossy(&output.stdout);
                 for line in output_str.lines() {
                     if line.trim().starts_with("Chipset Model:") {
@@ -599,7 +862,7 @@ impl SystemInfo {
                 }
             }
             
-            if let Ok(output) = Command::new("uname").arg("-m").output() {
+            if let Ok(output) = sysroot::run("uname", &["-m"]) {
                 let arch = String::from_utf8_lossy(&output.stdout).trim().to_string();
                 if arch.contains("arm64") || arch.contains("aarch64") {
                     return Ok("Apple GPU".to_string());
@@ -612,9 +875,81 @@ impl SystemInfo {
         Ok("unknown".to_string())
     }
 
+    pub fn get_sound_info() -> Result<SoundInfo, RFetchError> {
+        let runtime_dir = sysroot::var("XDG_RUNTIME_DIR").unwrap_or_default();
+        let runtime_path = std::path::Path::new(&runtime_dir);
+        let running: Vec<String> = process::list_processes().iter().map(|p| p.name()).collect();
+        let is_running = |name: &str| running.iter().any(|p| p == name);
+
+        let pipewire_socket = !runtime_dir.is_empty() && sysroot::exists(runtime_path.join("pipewire-0"));
+        let pulse_socket = !runtime_dir.is_empty() && sysroot::exists(runtime_path.join("pulse").join("native"));
+        let jack_socket = sysroot::read_dir("/dev/shm")
+            .map(|entries| entries.filter_map(|e| e.ok())
+                .any(|e| e.file_name().to_string_lossy().starts_with("jack")))
+            .unwrap_or(false);
+
+        let (server, server_version) = if pipewire_socket || is_running("pipewire") {
+            (Some("PipeWire"), probe_version("pipewire", &["--version"]))
+        } else if pulse_socket || is_running("pulseaudio") {
+            (Some("PulseAudio"), probe_version("pulseaudio", &["--version"]))
+        } else if jack_socket || is_running("jackd") || is_running("jackdbus") {
+            (Some("JACK"), probe_version("jackd", &["--version"]))
+        } else if sysroot::exists("/proc/asound") {
+            (Some("ALSA"), sysroot::read_to_string("/proc/asound/version").ok().and_then(|v| extract_version(&v)))
+        } else {
+            (None, None)
+        };
+
+        let default_sink = match server {
+            Some("PipeWire") => Self::get_pipewire_default_sink().or_else(Self::get_pulse_default_sink),
+            Some("PulseAudio") => Self::get_pulse_default_sink(),
+            _ => None,
+        };
+
+        let cards = sysroot::read_to_string("/proc/asound/cards")
+            .map(|content| Self::parse_sound_cards(&content))
+            .unwrap_or_default();
+
+        if server.is_none() && cards.is_empty() {
+            return Err(RFetchError::system_info("No audio server or sound card found"));
+        }
+
+        Ok(SoundInfo {
+            server: server.map(|s| s.to_string()),
+            server_version,
+            default_sink,
+            cards,
+        })
+    }
+
+    fn get_pipewire_default_sink() -> Option<String> {
+        let output = sysroot::run("wpctl", &["inspect", "@DEFAULT_AUDIO_SINK@"]).ok()?;
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .find_map(|line| {
+                let line = line.trim_start_matches(|c: char| c == '*' || c.is_whitespace());
+                line.strip_prefix("node.description = ")
+                    .map(|value| value.trim_matches('"').to_string())
+            })
+    }
+
+    fn get_pulse_default_sink() -> Option<String> {
+        let output = sysroot::run("pactl", &["get-default-sink"]).ok()?;
+        let sink = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        (output.status.success() && !sink.is_empty()).then_some(sink)
+    }
+
+    fn parse_sound_cards(content: &str) -> Vec<String> {
+        content
+            .lines()
+            .filter(|line| line.trim_start().starts_with(|c: char| c.is_ascii_digit()))
+            .filter_map(|line| line.split_once(" - ").map(|(_, name)| name.trim().to_string()))
+            .collect()
+    }
+
     #[cfg(target_os = "linux")]
-    fn get_memory_info() -> Result<MemoryInfo, RFetchError> {
-        if let Ok(content) = fs::read_to_string("/proc/meminfo") {
+    pub fn get_memory_info() -> Result<MemoryInfo, RFetchError> {
+        if let Ok(content) = sysroot::read_to_string("/proc/meminfo") {
             let mut total = 0u64;
             let mut available = 0u64;
 
@@ -651,8 +986,8 @@ impl SystemInfo {
     }
 
     #[cfg(target_os = "macos")]
-    fn get_memory_info() -> Result<MemoryInfo, RFetchError> {
-        if let Ok(output) = Command::new("vm_stat").output() {
+    pub fn get_memory_info() -> Result<MemoryInfo, RFetchError> {
+        if let Ok(output) = sysroot::run("vm_stat", &[]) {
             let output_str = String::from_utf8_lossy(&output.stdout);
             
             let mut page_size = 4096u64;
@@ -726,7 +1061,7 @@ impl SystemInfo {
     }
 
     #[cfg(target_os = "windows")]
-    fn get_memory_info() -> Result<MemoryInfo, RFetchError> {
+    pub fn get_memory_info() -> Result<MemoryInfo, RFetchError> {
         Ok(MemoryInfo {
             total: 0,
             used: 0,
@@ -736,8 +1071,8 @@ impl SystemInfo {
     }
 
     #[cfg(target_os = "ios")]
-    fn get_memory_info() -> Result<MemoryInfo, RFetchError> {
-        if let Ok(output) = Command::new("vm_stat").output() {
+    pub fn get_memory_info() -> Result<MemoryInfo, RFetchError> {
+        if let Ok(output) = sysroot::run("vm_stat", &[]) {
             let output_str = String::from_utf8_lossy(&output.stdout);
             
             let mut page_size = 4096u64;
@@ -805,12 +1140,12 @@ impl SystemInfo {
         })
     }
 
-    fn get_disk_info() -> Result<Vec<DiskInfo>, RFetchError> {
+    pub fn get_disk_info() -> Result<Vec<DiskInfo>, RFetchError> {
         let mut disks = Vec::new();
 
         #[cfg(target_os = "macos")]
         {
-            if let Ok(output) = Command::new("df").args(&["-h"]).output() {
+            if let Ok(output) = sysroot::run("df", &["-h"]) {
                 let output_str = String::from_utf8_lossy(&output.stdout);
                 for line in output_str.lines().skip(1) {
                     let parts: Vec<&str> = line.split_whitespace().collect();
@@ -834,34 +1169,30 @@ impl SystemInfo {
 
         #[cfg(target_os = "linux")]
         {
-            if let Ok(output) = Command::new("df").args(&["-h", "--output=source,target,size,used,avail,pcent,fstype"]).output() {
-                let output_str = String::from_utf8_lossy(&output.stdout);
-                for line in output_str.lines().skip(1) {
-                    let parts: Vec<&str> = line.split_whitespace().collect();
-                    if parts.len() >= 7 && !parts[0].starts_with("tmpfs") && !parts[0].starts_with("devtmpfs") {
-                        if parts[1] == "/" {
-                            disks.push(DiskInfo {
-                                device: parts[0].to_string(),
-                                mount_point: parts[1].to_string(),
-                                total: Self::parse_size_string(parts[2]),
-                                used: Self::parse_size_string(parts[3]),
-                                available: Self::parse_size_string(parts[4]),
-                                percentage: parts[5].trim_end_matches('%').parse().unwrap_or(0.0),
-                                filesystem: parts.get(6).unwrap_or(&"unknown").to_string(),
-                            });
-                            break;
-                        }
-                    }
-                }
+            if let Ok(usage) = sysroot::statvfs("/") {
+                let (device, filesystem) = sysroot::read_to_string("/proc/mounts")
+                    .ok()
+                    .and_then(|mounts| Self::parse_root_mount(&mounts))
+                    .unwrap_or_else(|| ("unknown".to_string(), "unknown".to_string()));
+                trace!("root filesystem is {} ({})", device, filesystem);
+                let used = usage.total.saturating_sub(usage.free);
+                // Like df, measure against the space unprivileged users can reach.
+                let usable = used + usage.available;
+                disks.push(DiskInfo {
+                    device,
+                    mount_point: "/".to_string(),
+                    total: usage.total,
+                    used,
+                    available: usage.available,
+                    percentage: if usable > 0 { used as f64 * 100.0 / usable as f64 } else { 0.0 },
+                    filesystem,
+                });
             }
         }
 
         #[cfg(target_os = "windows")]
         {
-            if let Ok(output) = Command::new("wmic")
-                .args(&["logicaldisk", "get", "size,freespace,caption", "/value"])
-                .output()
-            {
+            if let Ok(output) = sysroot::run("wmic", &["logicaldisk", "get", "size,freespace,caption", "/value"]) {
                 let output_str = String::from_utf8_lossy(&output.stdout);
                 let mut caption = String::new();
                 let mut size = 0u64;
@@ -901,7 +1232,7 @@ impl SystemInfo {
 
         #[cfg(target_os = "ios")]
         {
-            if let Ok(output) = Command::new("df").args(&["-h"]).output() {
+            if let Ok(output) = sysroot::run("df", &["-h"]) {
                 let output_str = String::from_utf8_lossy(&output.stdout);
                 for line in output_str.lines().skip(1) {
                     let parts: Vec<&str> = line.split_whitespace().collect();
@@ -936,6 +1267,7 @@ impl SystemInfo {
         Ok(disks)
     }
 
+    #[cfg(any(target_os = "macos", target_os = "ios"))]
     fn parse_size_string(size_str: &str) -> u64 {
         let size_str = size_str.trim();
         if size_str.is_empty() {
@@ -969,13 +1301,13 @@ impl SystemInfo {
         }
     }
 
-    fn get_battery_info() -> Result<BatteryInfo, RFetchError> {
+    pub fn get_battery_info() -> Result<BatteryInfo, RFetchError> {
         #[cfg(target_os = "linux")]
         {
             let battery_path = "/sys/class/power_supply/BAT0";
-            if let Ok(capacity) = fs::read_to_string(format!("{}/capacity", battery_path)) {
+            if let Ok(capacity) = sysroot::read_to_string(format!("{}/capacity", battery_path)) {
                 let percentage = capacity.trim().parse().unwrap_or(0);
-                let status = fs::read_to_string(format!("{}/status", battery_path))
+                let status = sysroot::read_to_string(format!("{}/status", battery_path))
                     .unwrap_or_else(|_| "Unknown".to_string())
                     .trim()
                     .to_string();
@@ -990,7 +1322,7 @@ impl SystemInfo {
 
         #[cfg(target_os = "macos")]
         {
-            if let Ok(output) = Command::new("pmset").args(&["-g", "batt"]).output() {
+            if let Ok(output) = sysroot::run("pmset", &["-g", "batt"]) {
                 let output_str = String::from_utf8_lossy(&output.stdout);
                 
                 let is_ac_power = output_str.contains("AC Power");
@@ -1048,7 +1380,7 @@ impl SystemInfo {
 
         #[cfg(target_os = "ios")]
         {
-            if let Ok(output) = Command::new("pmset").args(&["-g", "batt"]).output() {
+            if let Ok(output) = sysroot::run("pmset", &["-g", "batt"]) {
                 let output_str = String::from_utf8_lossy(&output.stdout);
                 
                 for line in output_str.lines() {
@@ -1089,40 +1421,104 @@ impl SystemInfo {
         Err(RFetchError::system_info("No battery found"))
     }
 
-    fn get_locale() -> String {
-        env::var("LANG").unwrap_or_else(|_| "unknown".to_string())
+    pub fn get_locale() -> LocaleInfo {
+        const LC_CATEGORIES: &[&str] = &[
+            "LC_CTYPE", "LC_NUMERIC", "LC_TIME", "LC_COLLATE", "LC_MONETARY",
+            "LC_MESSAGES", "LC_PAPER", "LC_NAME", "LC_ADDRESS", "LC_TELEPHONE",
+            "LC_MEASUREMENT", "LC_IDENTIFICATION",
+        ];
+
+        let read = |name: &str| sysroot::var(name).ok().filter(|value| !value.is_empty());
+        let lang = read("LANG");
+
+        let overrides = LC_CATEGORIES
+            .iter()
+            .filter_map(|name| read(name).map(|value| (name.to_string(), value)))
+            .filter(|(_, value)| Some(value) != lang.as_ref())
+            .collect();
+
+        LocaleInfo {
+            lang,
+            lc_all: read("LC_ALL"),
+            overrides,
+        }
     }
 
-    fn get_local_ip() -> Result<String, RFetchError> {
-        #[cfg(unix)]
-        {
-            if let Ok(output) = Command::new("hostname").arg("-I").output() {
-                let ip = String::from_utf8_lossy(&output.stdout)
-                    .split_whitespace()
-                    .next()
-                    .unwrap_or("unknown")
-                    .to_string();
-                return Ok(ip);
-            }
-        }
+    fn generate_color_bar() -> Vec<String> {
+        let colors = ["■"; 8];
+        colors.iter().map(|&s| s.to_string()).collect()
+    }
+}
 
-        Ok("unknown".to_string())
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_sound_cards() {
+        let content = " 0 [PCH            ]: HDA-Intel - HDA Intel PCH\n\
+                       \x20                     HDA Intel PCH at 0xf7f10000 irq 32\n\
+                       \x201 [NVidia         ]: HDA-Intel - HDA NVidia\n";
+        assert_eq!(SystemInfo::parse_sound_cards(content), vec!["HDA Intel PCH", "HDA NVidia"]);
     }
 
-    fn get_logged_users() -> Vec<String> {
-        if let Ok(output) = Command::new("who").output() {
-            let output_str = String::from_utf8_lossy(&output.stdout);
-            return output_str
-                .lines()
-                .filter_map(|line| line.split_whitespace().next())
-                .map(|s| s.to_string())
-                .collect();
+    #[cfg(all(unix, feature = "custom"))]
+    #[test]
+    fn test_gather_times_out_slow_modules() {
+        use crate::config::CustomModuleConfig;
+
+        let custom = |id: &str, command: &str| CustomModuleConfig {
+            id: id.to_string(),
+            label: id.to_string(),
+            command: Some(command.to_string()),
+            file: None,
+            regex: None,
+            timeout_ms: 10_000,
+            cache_ttl: 0,
+        };
+        let mut config = Config::default();
+        config.cache.enabled = false;
+        config.custom = vec![custom("slow", "sleep 10"), custom("fast", "echo hi")];
+        config.timeouts.modules.insert("slow".to_string(), 200);
+        config.select_modules(&["slow".to_string(), "fast".to_string()], &[], &[]).unwrap();
+
+        let started = Instant::now();
+        let info = SystemInfo::gather(&config);
+        assert!(started.elapsed() < Duration::from_secs(5));
+        let status = |id: &str| info.modules.iter().find(|report| report.id() == id).map(|report| &report.status);
+        assert!(matches!(status("slow"), Some(ModuleStatus::Failed(reason)) if reason == TIMEOUT));
+        assert!(matches!(status("fast"), Some(ModuleStatus::Ready(_))));
+    }
+
+    struct PanickingModule;
+
+    impl modules::Module for PanickingModule {
+        type Value = String;
+
+        fn id(&self) -> &str {
+            "panicking"
+        }
+
+        fn label(&self) -> &str {
+            "Panicking"
+        }
+
+        fn collect(&self, _config: &Config) -> Result<Option<String>, RFetchError> {
+            panic!("detector bug")
+        }
+
+        fn render(&self, value: &String, _display: &crate::display::DisplayManager) -> Vec<String> {
+            vec![value.clone()]
         }
-        Vec::new()
     }
 
-    fn generate_color_bar() -> Vec<String> {
-        let colors = ["■"; 8];
-        colors.iter().map(|&s| s.to_string()).collect()
+    // Run with `cargo test --release` too: the release profile must unwind for this to hold.
+    #[test]
+    fn test_gather_reports_panicking_modules() {
+        let mut config = Config::default();
+        config.cache.enabled = false;
+        let module: ModuleHandle = Arc::new(modules::Registered(PanickingModule));
+        let info = SystemInfo::gather_modules(&config, vec![module]);
+        assert!(matches!(&info.modules[0].status, ModuleStatus::Failed(reason) if reason == PANIC));
     }
-}
\ No newline at end of file
+}
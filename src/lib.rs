@@ -0,0 +1,139 @@
+//! rFetch as a library: gather system information with a [`Config`] and render it exactly like
+//! the `rfetch` binary does.
+//!
+//! ```no_run
+//! let config = rfetch::Config::default();
+//! let info = rfetch::gather(&config);
+//! print!("{}", rfetch::render(&info, &config, None)?);
+//! # Ok::<(), rfetch::RFetchError>(())
+//! ```
+//!
+//! Optional subsystems are behind Cargo features, all enabled by default: `custom`, `plugins`,
+//! `sensors`, `network`, `users` and `toolchains`. Disabling one removes its modules from the
+//! registry; the matching config sections are still accepted so config files stay portable.
+
+mod cache;
+mod config;
+#[cfg(feature = "custom")]
+mod custom;
+mod datetime;
+mod desktop;
+mod display;
+mod doctor;
+mod error;
+mod info;
+mod logo;
+mod modules;
+#[cfg(feature = "network")]
+mod network;
+mod pci;
+#[cfg(feature = "plugins")]
+mod plugins;
+mod process;
+mod security;
+#[cfg(feature = "sensors")]
+mod sensors;
+#[cfg(target_os = "linux")]
+mod sqlite;
+mod sysroot;
+mod tdl;
+mod themes;
+mod timings;
+#[cfg(feature = "toolchains")]
+mod toolchains;
+mod trace;
+#[cfg(feature = "users")]
+mod users;
+mod utils;
+
+use std::io::Write;
+
+pub use crate::cache::{CachePolicy, Invalidate};
+pub use crate::config::Config;
+pub use crate::display::DisplayManager;
+pub use crate::error::RFetchError;
+pub use crate::info::{ModuleReport, ModuleStatus, SystemInfo, JSON_SCHEMA_VERSION};
+pub use crate::modules::{AnyModule, Detail, Module, ModuleValue};
+pub use crate::tdl::{TdlFormat, TdlGenerator, TdlParser, TdlTheme, TdlValidator};
+pub use crate::themes::Theme;
+
+/// Helpers used by the `rfetch` binary. Not part of the library API and may change at any time.
+#[doc(hidden)]
+pub mod cli {
+    pub mod doctor {
+        pub use crate::doctor::{check_config, count, render, run, Level, Section};
+    }
+
+    pub mod logo {
+        pub use crate::logo::{find, get_logo, LOGOS};
+    }
+
+    pub mod modules {
+        pub use crate::modules::{registry, shadowed, MODULES};
+    }
+
+    pub mod themes {
+        pub use crate::themes::{list_themes, load_theme, AdvancedColor, Theme};
+    }
+
+    pub mod timings {
+        pub use crate::timings::table;
+    }
+
+    pub mod trace {
+        pub use crate::trace::init;
+    }
+}
+
+/// Runs every module enabled in `config`. Failing or slow modules are reported per module in
+/// [`SystemInfo::modules`] instead of failing the whole gather.
+pub fn gather(config: &Config) -> SystemInfo {
+    SystemInfo::gather(config)
+}
+
+/// Renders `info` the way the CLI prints it, honouring `config.display.output_format`. With
+/// `color_mode = "auto"` the result is uncolored, since a `String` is not a terminal.
+pub fn render(info: &SystemInfo, config: &Config, theme: Option<&Theme>) -> Result<String, RFetchError> {
+    display_manager(config, theme).with_terminal(false).render_to_string(info)
+}
+
+/// Like [`render`], but writes into any [`Write`] sink with a single flush. `color_mode = "auto"`
+/// colors the output when stdout is a terminal.
+pub fn render_to<W: Write>(
+    writer: &mut W,
+    info: &SystemInfo,
+    config: &Config,
+    theme: Option<&Theme>,
+) -> Result<(), RFetchError> {
+    display_manager(config, theme).render(info, writer)
+}
+
+fn display_manager<'a>(config: &'a Config, theme: Option<&'a Theme>) -> DisplayManager<'a> {
+    match theme {
+        Some(theme) => DisplayManager::with_theme(config, theme),
+        None => DisplayManager::new(config),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_json() {
+        let mut config = Config::default();
+        config.display.output_format = "json".to_string();
+        let info = SystemInfo {
+            user: "alice".to_string(),
+            hostname: "box".to_string(),
+            colors: Vec::new(),
+            modules: Vec::new(),
+        };
+
+        let rendered = render(&info, &config, None).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&rendered).unwrap();
+        assert_eq!(json["user"], "alice");
+        assert_eq!(json["hostname"], "box");
+        assert_eq!(json["schema_version"], info::JSON_SCHEMA_VERSION);
+    }
+}
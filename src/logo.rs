@@ -1,4 +1,25 @@
-use std::collections::HashMap;
+
+use crate::error::RFetchError;
+use crate::utils::suggest;
+
+/// Logo names, matched against the OS name by `auto`; anything else gets the generic logo.
+pub const LOGOS: &[&str] = &[
+    "alpine", "arch", "centos", "debian", "fedora", "gentoo", "ios", "macos", "manjaro", "opensuse",
+    "termux", "ubuntu", "windows", "generic",
+];
+
+/// Resolves a logo name given on the command line, suggesting the closest one on a typo.
+pub fn find(name: &str) -> Result<&'static str, RFetchError> {
+    let lower = name.to_lowercase();
+    if let Some(logo) = LOGOS.iter().find(|logo| **logo == lower) {
+        return Ok(logo);
+    }
+    let message = match suggest(&lower, LOGOS.iter().copied()) {
+        Some(suggestion) => format!("Unknown logo '{}'. Did you mean '{}'?", name, suggestion),
+        None => format!("Unknown logo '{}'. Use `rfetch logo list` to see the available logos.", name),
+    };
+    Err(RFetchError::display(message))
+}
 
 pub fn get_logo(os_name: &str, logo_type: &str) -> Vec<String> {
     match logo_type {
@@ -383,26 +404,3 @@ fn get_generic_logo() -> Vec<String> {
         "   |||||   ".to_string(),
     ]
 }
-
-pub fn get_color_codes() -> HashMap<String, String> {
-    let mut colors = HashMap::new();
-    colors.insert("black".to_string(), "\x1b[30m".to_string());
-    colors.insert("red".to_string(), "\x1b[31m".to_string());
-    colors.insert("green".to_string(), "\x1b[32m".to_string());
-    colors.insert("yellow".to_string(), "\x1b[33m".to_string());
-    colors.insert("blue".to_string(), "\x1b[34m".to_string());
-    colors.insert("magenta".to_string(), "\x1b[35m".to_string());
-    colors.insert("cyan".to_string(), "\x1b[36m".to_string());
-    colors.insert("white".to_string(), "\x1b[37m".to_string());
-    colors.insert("bright_black".to_string(), "\x1b[90m".to_string());
-    colors.insert("bright_red".to_string(), "\x1b[91m".to_string());
-    colors.insert("bright_green".to_string(), "\x1b[92m".to_string());
-    colors.insert("bright_yellow".to_string(), "\x1b[93m".to_string());
-    colors.insert("bright_blue".to_string(), "\x1b[94m".to_string());
-    colors.insert("bright_magenta".to_string(), "\x1b[95m".to_string());
-    colors.insert("bright_cyan".to_string(), "\x1b[96m".to_string());
-    colors.insert("bright_white".to_string(), "\x1b[97m".to_string());
-    colors.insert("reset".to_string(), "\x1b[0m".to_string());
-    colors.insert("bold".to_string(), "\x1b[1m".to_string());
-    colors
-}
\ No newline at end of file
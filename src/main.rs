@@ -1,131 +1,294 @@
-use clap::{Arg, Command};
+use clap::{Arg, ArgMatches, Command};
 use colored::*;
-use std::process;
+use std::fs::{self, File};
 use std::io::{self, Write};
+use std::path::Path;
 
-mod config;
-mod display;
-mod error;
-mod info;
-mod logo;
-mod tdl;
-mod themes;
-mod utils;
-
-use crate::config::Config;
-use crate::display::DisplayManager;
-use crate::error::RFetchError;
-use crate::info::SystemInfo;
-use crate::tdl::{TdlParser, TdlGenerator};
-use crate::themes::{load_theme, list_themes};
+use rfetch::cli::themes::{self, load_theme, list_themes, AdvancedColor};
+use rfetch::cli::{doctor, logo, modules, timings, trace};
+use rfetch::{Config, DisplayManager, RFetchError, TdlFormat, TdlGenerator, TdlParser, TdlValidator};
 
 fn main() {
     if let Err(e) = run() {
         eprintln!("{}: {}", "Error".red().bold(), e);
-        process::exit(1);
+        std::process::exit(1);
     }
 }
 
-fn run() -> Result<(), RFetchError> {
-    let matches = Command::new("rFetch")
+fn cli() -> Command {
+    Command::new("rFetch")
         .version(env!("CARGO_PKG_VERSION"))
         .author("rFetch Team")
         .about("A fast and beautiful system information tool")
+        .args_conflicts_with_subcommands(true)
         .arg(
             Arg::new("config")
                 .short('c')
                 .long("config")
                 .value_name("FILE")
                 .help("Use custom configuration file")
+                .global(true)
         )
-        .arg(
-            Arg::new("theme")
-                .short('t')
-                .long("theme")
-                .value_name("THEME")
-                .help("Use a specific theme (default, minimal, neon, retro) or path to custom TDL file")
-        )
+        .args(show_args())
         .arg(
             Arg::new("list-themes")
                 .long("list-themes")
-                .help("List all available themes")
+                .help("List all available themes (same as `rfetch theme list`)")
                 .action(clap::ArgAction::SetTrue)
         )
         .arg(
             Arg::new("generate-template")
                 .long("generate-template")
                 .value_name("FORMAT")
-                .help("Generate a TDL theme template (json, yaml, toml)")
+                .help("Generate a TDL theme template (same as `rfetch theme new --format FORMAT`)")
         )
         .arg(
-            Arg::new("logo")
-                .short('l')
-                .long("logo")
-                .value_name("LOGO")
-                .help("Display specific logo (auto, ascii, small, none)")
+            Arg::new("list-modules")
+                .long("list-modules")
+                .help("List all available information modules")
+                .action(clap::ArgAction::SetTrue)
         )
-        .arg(
-            Arg::new("color")
-                .long("color")
-                .value_name("WHEN")
-                .help("When to use colors (auto, always, never)")
-                .default_value("auto")
+        .subcommand(
+            Command::new("show")
+                .about("Fetch and display system information (the default when no subcommand is given)")
+                .args(show_args())
         )
-        .arg(
-            Arg::new("json")
-                .short('j')
-                .long("json")
-                .help("Output in JSON format")
-                .action(clap::ArgAction::SetTrue)
+        .subcommand(
+            Command::new("theme")
+                .about("List, inspect, validate, create and convert themes")
+                .subcommand_required(true)
+                .arg_required_else_help(true)
+                .subcommand(Command::new("list").about("List the built-in themes"))
+                .subcommand(
+                    Command::new("show")
+                        .about("Show the colors and display settings of a theme")
+                        .arg(Arg::new("theme").value_name("THEME").required(true).help("Built-in theme name or TDL file"))
+                )
+                .subcommand(
+                    Command::new("validate")
+                        .about("Check a TDL theme file for errors")
+                        .arg(Arg::new("file").value_name("FILE").required(true))
+                )
+                .subcommand(
+                    Command::new("new")
+                        .about("Write a TDL theme template to start a new theme from")
+                        .arg(format_arg("format").default_value("toml"))
+                        .arg(output_arg())
+                )
+                .subcommand(
+                    Command::new("convert")
+                        .about("Convert a TDL theme file to another format")
+                        .arg(Arg::new("file").value_name("FILE").required(true))
+                        .arg(format_arg("to").required(true))
+                        .arg(output_arg())
+                )
         )
-        .arg(
-            Arg::new("minimal")
-                .short('m')
-                .long("minimal")
-                .help("Show minimal information")
-                .action(clap::ArgAction::SetTrue)
+        .subcommand(
+            Command::new("config")
+                .about("Create, print, locate and check the configuration file")
+                .subcommand_required(true)
+                .arg_required_else_help(true)
+                .subcommand(
+                    Command::new("init")
+                        .about("Write a configuration file with the default settings")
+                        .arg(
+                            Arg::new("force")
+                                .long("force")
+                                .help("Overwrite an existing file")
+                                .action(clap::ArgAction::SetTrue)
+                        )
+                )
+                .subcommand(Command::new("print").about("Print the configuration in effect, defaults included"))
+                .subcommand(Command::new("path").about("Print the path of the configuration file in use"))
+                .subcommand(Command::new("check").about("Check the configuration file and its theme for problems"))
         )
-        .arg(
-            Arg::new("verbose")
-                .short('v')
-                .long("verbose")
-                .help("Show verbose information")
-                .action(clap::ArgAction::SetTrue)
+        .subcommand(
+            Command::new("logo")
+                .about("List and print logos")
+                .subcommand_required(true)
+                .arg_required_else_help(true)
+                .subcommand(Command::new("list").about("List the available logos"))
+                .subcommand(
+                    Command::new("print")
+                        .about("Print a logo")
+                        .arg(Arg::new("name").value_name("NAME").required(true))
+                        .arg(
+                            Arg::new("type")
+                                .long("type")
+                                .value_name("TYPE")
+                                .help("Logo size (auto, small, ascii)")
+                                .value_parser(["auto", "small", "ascii"])
+                                .default_value("auto")
+                        )
+                )
         )
-        .arg(
-            Arg::new("clear")
-                .long("clr")
-                .help("Clear terminal before displaying information")
-                .action(clap::ArgAction::SetTrue)
+        .subcommand(
+            Command::new("doctor")
+                .about("Check helper programs, system files, terminal, locale, config and theme for problems")
         )
-        .get_matches();
+}
 
-    if let Some(format) = matches.get_one::<String>("generate-template") {
-        let template = TdlGenerator::generate_template(format);
-        println!("{}", template);
-        return Ok(());
-    }
+/// Flags of the fetch itself, accepted both by `rfetch` and `rfetch show`.
+fn show_args() -> Vec<Arg> {
+    vec![
+        Arg::new("theme")
+            .short('t')
+            .long("theme")
+            .value_name("THEME")
+            .help("Use a specific theme (default, minimal, neon, retro) or path to custom TDL file"),
+        Arg::new("logo")
+            .short('l')
+            .long("logo")
+            .value_name("LOGO")
+            .help("Display specific logo (auto, ascii, small, none)"),
+        Arg::new("color")
+            .long("color")
+            .value_name("WHEN")
+            .help("When to use colors (auto, always, never)")
+            .default_value("auto"),
+        Arg::new("json")
+            .short('j')
+            .long("json")
+            .help("Output in JSON format")
+            .action(clap::ArgAction::SetTrue),
+        Arg::new("output")
+            .short('o')
+            .long("output")
+            .value_name("FILE")
+            .help("Write the output to FILE instead of stdout"),
+        Arg::new("minimal")
+            .short('m')
+            .long("minimal")
+            .help("Show minimal information")
+            .action(clap::ArgAction::SetTrue),
+        Arg::new("verbose")
+            .short('v')
+            .long("verbose")
+            .help("Show verbose information")
+            .action(clap::ArgAction::SetTrue),
+        Arg::new("clear")
+            .long("clr")
+            .help("Clear terminal before displaying information")
+            .action(clap::ArgAction::SetTrue),
+        Arg::new("modules")
+            .long("modules")
+            .value_name("IDS")
+            .help("Show only these modules, in this order (comma-separated, see --list-modules)")
+            .value_delimiter(','),
+        Arg::new("show")
+            .long("show")
+            .value_name("IDS")
+            .help("Also show these modules (comma-separated, repeatable)")
+            .value_delimiter(',')
+            .action(clap::ArgAction::Append),
+        Arg::new("hide")
+            .long("hide")
+            .value_name("IDS")
+            .help("Hide these modules (comma-separated, repeatable)")
+            .value_delimiter(',')
+            .action(clap::ArgAction::Append),
+        Arg::new("sysroot")
+            .long("sysroot")
+            .value_name("DIR")
+            .help("Read system information from a captured /proc, /sys and /etc tree"),
+        Arg::new("no-cache")
+            .long("no-cache")
+            .help("Neither read nor write cached module results")
+            .action(clap::ArgAction::SetTrue),
+        Arg::new("refresh")
+            .long("refresh")
+            .help("Recollect cached modules and store the fresh results")
+            .action(clap::ArgAction::SetTrue),
+        Arg::new("timings")
+            .long("timings")
+            .help("Show how long each module took and which commands it ran")
+            .action(clap::ArgAction::SetTrue),
+        Arg::new("debug")
+            .long("debug")
+            .help("Log on stderr which files, commands and variables each module consulted (also RFETCH_LOG)")
+            .action(clap::ArgAction::SetTrue),
+        Arg::new("strict")
+            .long("strict")
+            .help("Exit with a non-zero status if any module fails or times out")
+            .action(clap::ArgAction::SetTrue),
+    ]
+}
+
+fn format_arg(name: &'static str) -> Arg {
+    Arg::new(name)
+        .long(name)
+        .value_name("FORMAT")
+        .help("Theme format (toml, yaml, json)")
+        .value_parser(["toml", "yaml", "yml", "json"])
+}
 
-    if matches.get_flag("list-themes") {
-        println!("Available themes:");
-        for theme_name in list_themes() {
-            if let Some(theme) = load_theme(&theme_name) {
-                println!("  {} - {}", theme_name.cyan().bold(), theme.description);
+fn output_arg() -> Arg {
+    Arg::new("output")
+        .short('o')
+        .long("output")
+        .value_name("FILE")
+        .help("Write to FILE instead of stdout")
+}
+
+fn run() -> Result<(), RFetchError> {
+    let matches = cli().get_matches();
+
+    let fetch = match matches.subcommand() {
+        Some(("show", show)) => show,
+        _ => &matches,
+    };
+    trace::init(fetch.get_flag("debug"));
+
+    match matches.subcommand() {
+        Some(("show", show_matches)) => show(show_matches),
+        Some(("theme", theme_matches)) => theme_command(theme_matches),
+        Some(("config", config_matches)) => config_command(config_matches),
+        Some(("logo", logo_matches)) => logo_command(logo_matches),
+        Some(("doctor", doctor_matches)) => {
+            let sections = doctor::run(doctor_matches.get_one::<String>("config"));
+            print!("{}", doctor::render(&sections));
+            exit_on_failures(&sections);
+            Ok(())
+        }
+        _ => {
+            // These flags predate the subcommands and stay as aliases.
+            if let Some(format) = matches.get_one::<String>("generate-template") {
+                println!("{}", TdlGenerator::generate_template(format));
+                return Ok(());
             }
+
+            if matches.get_flag("list-modules") {
+                let config = Config::load(matches.get_one::<String>("config"))?;
+                println!("Available modules:");
+                for module in modules::registry(&config) {
+                    let state = if config.info.is_enabled(module.as_ref()) { "on" } else { "off" };
+                    println!("  {:<12} {:<12} {}", module.id().cyan().bold(), module.label(), state);
+                }
+                return Ok(());
+            }
+
+            if matches.get_flag("list-themes") {
+                print_theme_list();
+                return Ok(());
+            }
+
+            show(&matches)
         }
-        return Ok(());
     }
+}
 
+fn show(matches: &ArgMatches) -> Result<(), RFetchError> {
     let config_path = matches.get_one::<String>("config");
     let mut config = Config::load(config_path)?;
 
     let mut loaded_theme: Option<themes::Theme> = None;
-    if let Some(theme_name) = matches.get_one::<String>("theme") {
-        let theme = if std::path::Path::new(theme_name).exists() {
-            match TdlParser::parse_file(theme_name) {
-                Ok(tdl_theme) => Some(TdlParser::to_theme(tdl_theme)),
+    let theme_name = matches.get_one::<String>("theme").cloned().or_else(|| config.display.theme.clone());
+    if let Some(theme_name) = &theme_name {
+        let theme = if Path::new(theme_name).exists() {
+            match TdlParser::load(theme_name) {
+                Ok(theme) => Some(theme),
                 Err(e) => {
-                    eprintln!("{}: Failed to load custom theme '{}': {}", 
+                    eprintln!("{}: Failed to load custom theme '{}': {}",
                              "Error".red().bold(), theme_name, e);
                     None
                 }
@@ -135,20 +298,12 @@ fn run() -> Result<(), RFetchError> {
         };
 
         if let Some(theme) = theme {
-            config.colors.title = theme.colors.title.clone();
-            config.colors.subtitle = theme.colors.subtitle.clone();
-            config.colors.key = theme.colors.key.clone();
-            config.colors.value = theme.colors.value.clone();
-            config.colors.separator = theme.colors.separator.clone();
-            config.colors.logo = theme.colors.logo.clone();
-            
-            config.display.logo_type = theme.display.logo_type.clone();
-            config.display.separator = theme.display.separator.clone();
-            config.display.padding = theme.display.padding;
-            
+            config.apply_theme(&theme);
+            config.display.theme = Some(theme_name.clone());
+
             loaded_theme = Some(theme);
-        } else if !std::path::Path::new(theme_name).exists() {
-            eprintln!("{}: Unknown theme '{}'. Use --list-themes to see available themes.", 
+        } else if !Path::new(theme_name).exists() {
+            eprintln!("{}: Unknown theme '{}'. Use `rfetch theme list` to see available themes.",
                      "Warning".yellow().bold(), theme_name);
         }
     }
@@ -156,11 +311,17 @@ fn run() -> Result<(), RFetchError> {
     if let Some(logo) = matches.get_one::<String>("logo") {
         config.display.logo_type = logo.clone();
     }
-    
+
     if let Some(color) = matches.get_one::<String>("color") {
         config.display.color_mode = color.clone();
     }
 
+    // `colored` disables itself when stdout is not a terminal, which would defeat `--color always`
+    // for pipes and `--output`.
+    if config.display.color_mode == "always" {
+        colored::control::set_override(true);
+    }
+
     if matches.get_flag("json") {
         config.display.output_format = "json".to_string();
     }
@@ -175,33 +336,257 @@ fn run() -> Result<(), RFetchError> {
         config.apply_verbose();
     }
 
+    let ids = |id: &str| -> Vec<String> {
+        matches.get_many::<String>(id).map(|ids| ids.cloned().collect()).unwrap_or_default()
+    };
+    config.select_modules(&ids("modules"), &ids("show"), &ids("hide"))?;
+    for shadowed in modules::shadowed(&config) {
+        eprintln!("{}: {}", "Warning".yellow().bold(), shadowed);
+    }
+
+    if let Some(sysroot) = matches.get_one::<String>("sysroot") {
+        if !Path::new(sysroot).is_dir() {
+            return Err(RFetchError::config(format!("Sysroot '{}' is not a directory", sysroot)));
+        }
+        config.sysroot = Some(sysroot.into());
+    }
+
+    if matches.get_flag("timings") {
+        config.display.timings = true;
+    }
+
+    if matches.get_flag("no-cache") {
+        config.cache.enabled = false;
+    }
+    config.cache.refresh = matches.get_flag("refresh");
+
     if matches.get_flag("clear") {
         clear_terminal();
     }
 
-    let system_info = SystemInfo::gather(&config)?;
+    let system_info = rfetch::gather(&config);
 
-    let display_manager = if let Some(ref theme) = loaded_theme {
-        DisplayManager::with_theme(&config, theme)
-    } else {
-        DisplayManager::new(&config)
+    let display_manager = match &loaded_theme {
+        Some(theme) => DisplayManager::with_theme(&config, theme),
+        None => DisplayManager::new(&config),
     };
-    display_manager.display(&system_info)?;
+    match matches.get_one::<String>("output") {
+        Some(path) => {
+            let mut file = File::create(path)
+                .map_err(|e| RFetchError::display(format!("Cannot write to '{}': {}", path, e)))?;
+            display_manager.with_terminal(false).render(&system_info, &mut file)?;
+        }
+        None => display_manager.display(&system_info)?,
+    }
+
+    if config.display.timings && config.display.output_format != "json" {
+        eprint!("{}", timings::table(&system_info));
+    }
+
+    let errors = system_info.errors();
+    if matches.get_flag("strict") && !errors.is_empty() {
+        let failed: Vec<&str> = errors.keys().copied().collect();
+        return Err(RFetchError::system_info(format!("Modules failed: {}", failed.join(", "))));
+    }
 
     Ok(())
 }
 
+fn theme_command(matches: &ArgMatches) -> Result<(), RFetchError> {
+    match matches.subcommand() {
+        Some(("list", _)) => {
+            print_theme_list();
+            Ok(())
+        }
+        Some(("show", show)) => {
+            let name = show.get_one::<String>("theme").expect("required");
+            let theme = match load_theme(name) {
+                Some(theme) => theme,
+                None if Path::new(name).exists() => TdlParser::load(name)?,
+                None => {
+                    return Err(RFetchError::theme(format!(
+                        "Unknown theme '{}'. Use `rfetch theme list` to see available themes.",
+                        name
+                    )))
+                }
+            };
+            print_theme(&theme);
+            Ok(())
+        }
+        Some(("validate", validate)) => {
+            let file = validate.get_one::<String>("file").expect("required");
+            let tdl = TdlParser::parse_file(file).map_err(|e| RFetchError::theme(format!("{}: {}", file, e)))?;
+            match TdlValidator::validate(&tdl) {
+                Ok(()) => {
+                    println!("{} is a valid theme", file);
+                    Ok(())
+                }
+                Err(errors) => {
+                    for error in &errors {
+                        eprintln!("  {} {}", "-".red(), error);
+                    }
+                    Err(RFetchError::theme(format!("{} has {} problem(s)", file, errors.len())))
+                }
+            }
+        }
+        Some(("new", new)) => {
+            let format = new.get_one::<String>("format").expect("has default");
+            write_output(new, &TdlGenerator::generate_template(format))
+        }
+        Some(("convert", convert)) => {
+            let file = convert.get_one::<String>("file").expect("required");
+            let format = convert.get_one::<String>("to").and_then(|to| TdlFormat::from_name(to)).expect("required");
+            let content = fs::read_to_string(file)?;
+            write_output(convert, &TdlParser::convert(&content, format)?)
+        }
+        _ => unreachable!("subcommand_required"),
+    }
+}
+
+fn config_command(matches: &ArgMatches) -> Result<(), RFetchError> {
+    let config_path = matches
+        .subcommand()
+        .and_then(|(_, sub)| sub.get_one::<String>("config"))
+        .or_else(|| matches.get_one::<String>("config"));
+    let path = match config_path {
+        Some(path) => path.clone(),
+        None => Config::default_config_path()?,
+    };
+
+    match matches.subcommand() {
+        Some(("init", init)) => {
+            if Path::new(&path).exists() && !init.get_flag("force") {
+                return Err(RFetchError::config(format!("{} already exists; pass --force to overwrite it", path)));
+            }
+            Config::default().save(Some(&path))?;
+            println!("Wrote {}", path);
+            Ok(())
+        }
+        Some(("print", _)) => {
+            let config = Config::load(Some(&path))?;
+            let content = toml::to_string_pretty(&config)
+                .map_err(|e| RFetchError::config(format!("Failed to serialize config: {}", e)))?;
+            print!("{}", content);
+            Ok(())
+        }
+        Some(("path", _)) => {
+            println!("{}", path);
+            if !Path::new(&path).exists() {
+                eprintln!("(does not exist; the defaults are used. Create it with `rfetch config init`)");
+            }
+            Ok(())
+        }
+        Some(("check", _)) => {
+            let sections = doctor::check_config(Some(&path));
+            print!("{}", doctor::render(&sections));
+            exit_on_failures(&sections);
+            Ok(())
+        }
+        _ => unreachable!("subcommand_required"),
+    }
+}
+
+fn logo_command(matches: &ArgMatches) -> Result<(), RFetchError> {
+    match matches.subcommand() {
+        Some(("list", _)) => {
+            println!("Available logos:");
+            for name in logo::LOGOS {
+                println!("  {}", name.cyan().bold());
+            }
+            Ok(())
+        }
+        Some(("print", print)) => {
+            let name = logo::find(print.get_one::<String>("name").expect("required"))?;
+            let logo_type = print.get_one::<String>("type").expect("has default");
+            let config = Config::load(print.get_one::<String>("config"))?;
+            for line in logo::get_logo(name, logo_type) {
+                println!("{}", line.color(config.colors.logo.base.as_str()));
+            }
+            Ok(())
+        }
+        _ => unreachable!("subcommand_required"),
+    }
+}
+
+fn print_theme_list() {
+    println!("Available themes:");
+    for theme_name in list_themes() {
+        if let Some(theme) = load_theme(&theme_name) {
+            println!("  {} - {}", theme_name.cyan().bold(), theme.description);
+        }
+    }
+}
+
+fn print_theme(theme: &themes::Theme) {
+    println!("{} {} by {}", theme.name.cyan().bold(), theme.version, theme.author);
+    println!("{}", theme.description);
+    println!();
+
+    let colors = &theme.colors;
+    let mut roles: Vec<(&str, &AdvancedColor)> = vec![
+        ("title", &colors.title),
+        ("subtitle", &colors.subtitle),
+        ("key", &colors.key),
+        ("value", &colors.value),
+        ("separator", &colors.separator),
+        ("logo", &colors.logo),
+    ];
+    let optional = [
+        ("accent", &colors.accent),
+        ("background", &colors.background),
+        ("warning", &colors.warning),
+        ("error", &colors.error),
+    ];
+    roles.extend(optional.iter().filter_map(|(role, color)| color.as_ref().map(|color| (*role, color))));
+
+    println!("{}", "Colors".bold());
+    for (role, color) in roles {
+        let mut description = color.base.clone();
+        if let Some((r, g, b)) = color.rgb {
+            description.push_str(&format!(" #{:02x}{:02x}{:02x}", r, g, b));
+        }
+        if !color.effects.is_empty() {
+            let effects: Vec<String> = color.effects.iter().map(|effect| format!("{:?}", effect).to_lowercase()).collect();
+            description.push_str(&format!(" ({})", effects.join(", ")));
+        }
+        println!("  {:<11} {}", role, description.color(color.base.as_str()));
+    }
+
+    println!("{}", "Display".bold());
+    println!("  {:<11} {}", "logo", theme.display.logo_type);
+    println!("  {:<11} {:?}", "separator", theme.display.separator);
+    println!("  {:<11} {}", "padding", theme.display.padding);
+    println!("  {:<11} {}", "layout", theme.display.layout);
+}
+
+fn write_output(matches: &ArgMatches, content: &str) -> Result<(), RFetchError> {
+    match matches.get_one::<String>("output") {
+        Some(path) => {
+            fs::write(path, content).map_err(|e| RFetchError::display(format!("Cannot write to '{}': {}", path, e)))?;
+            println!("Wrote {}", path);
+        }
+        None => println!("{}", content),
+    }
+    Ok(())
+}
+
+fn exit_on_failures(sections: &[doctor::Section]) {
+    if doctor::count(sections, doctor::Level::Fail) > 0 {
+        std::process::exit(1);
+    }
+}
+
 fn clear_terminal() {
     print!("\x1B[2J\x1B[1;1H");
     io::stdout().flush().unwrap_or(());
-    
+
     #[cfg(target_os = "windows")]
     {
         let _ = std::process::Command::new("cls").status();
     }
-    
+
     #[cfg(not(target_os = "windows"))]
     {
         let _ = std::process::Command::new("clear").status();
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,1027 @@
+#[cfg(feature = "network")]
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::any::Any;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::cache::{CachePolicy, Invalidate};
+use crate::config::Config;
+#[cfg(feature = "custom")]
+use crate::custom::custom_modules;
+use crate::datetime::{get_date_info, DateInfo};
+use crate::desktop::{detect_desktop, detect_session_type, DesktopInfo};
+use crate::display::DisplayManager;
+use crate::error::RFetchError;
+use crate::info::{BatteryInfo, DiskInfo, LocaleInfo, MemoryInfo, SoundInfo, SystemInfo, UptimeInfo};
+#[cfg(feature = "network")]
+use crate::network::{get_local_ip, get_network_info, NetworkInfo};
+#[cfg(feature = "plugins")]
+use crate::plugins::plugin_modules;
+use crate::security::{get_security_info, SecurityInfo};
+#[cfg(feature = "sensors")]
+use crate::sensors::{get_sensors, SensorKind, SensorsInfo};
+#[cfg(feature = "toolchains")]
+use crate::toolchains::{find_probes, get_toolchains, ToolchainInfo};
+#[cfg(feature = "users")]
+use crate::users::{format_compact, get_users_info, UsersInfo};
+use crate::utils::{count_packages, format_bytes, package_databases, get_shell, get_terminal, with_version};
+
+pub type ModuleValue = Box<dyn Any + Send + Sync>;
+
+pub type ModuleHandle = Arc<dyn AnyModule>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Detail {
+    /// Shown by default and kept by `--minimal`.
+    Basic,
+    /// Hidden by default, enabled by `--verbose`.
+    Verbose,
+    /// Hidden by default and only enabled explicitly.
+    OptIn,
+}
+
+pub trait Module: Send + Sync + 'static {
+    type Value: Serialize + DeserializeOwned + Send + Sync + 'static;
+
+    fn id(&self) -> &str;
+
+    fn label(&self) -> &str;
+
+    fn detail(&self) -> Detail {
+        Detail::Basic
+    }
+
+    /// Overrides the default gather timeout when `[timeouts.modules]` has no entry for this module.
+    fn timeout(&self) -> Option<Duration> {
+        None
+    }
+
+    /// Lets the value be reused across runs; see [`CachePolicy`]. `[cache.ttl]` overrides the TTL.
+    fn cache(&self, _config: &Config) -> Option<CachePolicy> {
+        None
+    }
+
+    /// Returns `Ok(None)` when the module has nothing to report on this machine.
+    fn collect(&self, config: &Config) -> Result<Option<Self::Value>, RFetchError>;
+
+    fn render(&self, value: &Self::Value, display: &DisplayManager) -> Vec<String>;
+
+    fn to_json(&self, value: &Self::Value) -> serde_json::Value {
+        serde_json::to_value(value).unwrap_or(serde_json::Value::Null)
+    }
+}
+
+/// Type-erased view of a [`Module`], used by the registry and the gather loop.
+pub trait AnyModule: Send + Sync {
+    fn id(&self) -> &str;
+    fn label(&self) -> &str;
+    fn detail(&self) -> Detail;
+    fn timeout(&self) -> Option<Duration>;
+    fn cache(&self, config: &Config) -> Option<CachePolicy>;
+    fn collect(&self, config: &Config) -> Result<Option<ModuleValue>, RFetchError>;
+    fn render(&self, value: &ModuleValue, display: &DisplayManager) -> Vec<String>;
+    fn to_json(&self, value: &ModuleValue) -> serde_json::Value;
+    /// Plain serde form of the value for the cache, independent of a custom `to_json`.
+    fn store(&self, value: &ModuleValue) -> Option<serde_json::Value>;
+    fn restore(&self, json: serde_json::Value) -> Option<ModuleValue>;
+
+    fn default_enabled(&self) -> bool {
+        self.detail() == Detail::Basic
+    }
+}
+
+/// Wraps a [`Module`] so it can be stored in [`MODULES`].
+pub struct Registered<M>(pub M);
+
+impl<M: Module> AnyModule for Registered<M> {
+    fn id(&self) -> &str {
+        self.0.id()
+    }
+
+    fn label(&self) -> &str {
+        self.0.label()
+    }
+
+    fn detail(&self) -> Detail {
+        self.0.detail()
+    }
+
+    fn timeout(&self) -> Option<Duration> {
+        self.0.timeout()
+    }
+
+    fn cache(&self, config: &Config) -> Option<CachePolicy> {
+        self.0.cache(config)
+    }
+
+    fn collect(&self, config: &Config) -> Result<Option<ModuleValue>, RFetchError> {
+        Ok(self.0.collect(config)?.map(|value| Box::new(value) as ModuleValue))
+    }
+
+    fn render(&self, value: &ModuleValue, display: &DisplayManager) -> Vec<String> {
+        match value.downcast_ref::<M::Value>() {
+            Some(value) => self.0.render(value, display),
+            None => Vec::new(),
+        }
+    }
+
+    fn to_json(&self, value: &ModuleValue) -> serde_json::Value {
+        match value.downcast_ref::<M::Value>() {
+            Some(value) => self.0.to_json(value),
+            None => serde_json::Value::Null,
+        }
+    }
+
+    fn store(&self, value: &ModuleValue) -> Option<serde_json::Value> {
+        serde_json::to_value(value.downcast_ref::<M::Value>()?).ok()
+    }
+
+    fn restore(&self, json: serde_json::Value) -> Option<ModuleValue> {
+        serde_json::from_value::<M::Value>(json).ok().map(|value| Box::new(value) as ModuleValue)
+    }
+}
+
+lazy_static::lazy_static! {
+    /// Every built-in module, in default display order.
+    pub static ref MODULES: Vec<ModuleHandle> = builtins();
+}
+
+fn builtins() -> Vec<ModuleHandle> {
+    let mut modules: Vec<ModuleHandle> = vec![
+        Arc::new(Registered(TextModule { id: "os", label: "OS", detect: SystemInfo::get_os_info, cache: None })),
+        Arc::new(Registered(TextModule {
+            id: "kernel",
+            label: "Kernel",
+            detect: SystemInfo::get_kernel_info,
+            cache: HARDWARE,
+        })),
+        Arc::new(Registered(SecurityModule)),
+        Arc::new(Registered(UptimeModule)),
+        Arc::new(Registered(PackagesModule)),
+        Arc::new(Registered(TextModule { id: "shell", label: "Shell", detect: || Ok(get_shell()), cache: None })),
+        Arc::new(Registered(TextModule {
+            id: "resolution",
+            label: "Resolution",
+            detect: SystemInfo::get_resolution,
+            cache: None,
+        })),
+        Arc::new(Registered(DesktopModule)),
+        Arc::new(Registered(WindowManagerModule)),
+        Arc::new(Registered(SessionModule)),
+        Arc::new(Registered(TextModule { id: "terminal", label: "Terminal", detect: || Ok(get_terminal()), cache: None })),
+        Arc::new(Registered(TextModule { id: "cpu", label: "CPU", detect: SystemInfo::get_cpu_info, cache: HARDWARE })),
+        Arc::new(Registered(TextModule { id: "gpu", label: "GPU", detect: SystemInfo::get_gpu_info, cache: HARDWARE })),
+        Arc::new(Registered(SoundModule)),
+        Arc::new(Registered(MemoryModule)),
+        Arc::new(Registered(DiskModule)),
+        Arc::new(Registered(BatteryModule)),
+    ];
+    #[cfg(feature = "sensors")]
+    modules.push(Arc::new(Registered(SensorsModule)));
+    modules.push(Arc::new(Registered(LocaleModule)));
+    #[cfg(feature = "network")]
+    modules.push(Arc::new(Registered(LocalIpModule)));
+    #[cfg(feature = "users")]
+    modules.push(Arc::new(Registered(UsersModule)));
+    #[cfg(feature = "toolchains")]
+    modules.push(Arc::new(Registered(ToolchainsModule)));
+    modules.push(Arc::new(Registered(DateModule)));
+    modules
+}
+
+#[cfg(test)]
+pub fn find(id: &str) -> Option<&'static ModuleHandle> {
+    MODULES.iter().find(|module| module.id() == id)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Origin {
+    BuiltIn,
+    Custom,
+    Plugin,
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Origin::BuiltIn => write!(f, "built-in module"),
+            Origin::Custom => write!(f, "custom module"),
+            Origin::Plugin => write!(f, "plugin"),
+        }
+    }
+}
+
+/// A custom module or plugin left out of the registry because an earlier module has its id.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Shadowed {
+    pub id: String,
+    pub origin: Origin,
+    pub by: Origin,
+}
+
+impl fmt::Display for Shadowed {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} '{}' is ignored: a {} already uses that id", self.origin, self.id, self.by)
+    }
+}
+
+/// Built-in modules followed by config-defined custom modules and discovered plugins. Later
+/// entries never shadow an earlier module with the same id; see [`shadowed`].
+pub fn registry(config: &Config) -> Vec<ModuleHandle> {
+    resolve(config).0
+}
+
+/// The custom modules and plugins that [`registry`] leaves out.
+pub fn shadowed(config: &Config) -> Vec<Shadowed> {
+    resolve(config).1
+}
+
+#[cfg_attr(not(any(feature = "custom", feature = "plugins")), allow(unused_mut, unused_variables))]
+fn resolve(config: &Config) -> (Vec<ModuleHandle>, Vec<Shadowed>) {
+    let mut modules: Vec<(ModuleHandle, Origin)> =
+        MODULES.iter().map(|module| (Arc::clone(module), Origin::BuiltIn)).collect();
+    let mut extras: Vec<(ModuleHandle, Origin)> = Vec::new();
+    #[cfg(feature = "custom")]
+    extras.extend(custom_modules(config).into_iter().map(|module| (module, Origin::Custom)));
+    #[cfg(feature = "plugins")]
+    extras.extend(plugin_modules(config).into_iter().map(|module| (module, Origin::Plugin)));
+
+    let mut shadowed = Vec::new();
+    for (extra, origin) in extras {
+        match modules.iter().find(|(module, _)| module.id() == extra.id()) {
+            Some((_, by)) => shadowed.push(Shadowed { id: extra.id().to_string(), origin, by: *by }),
+            None => modules.push((extra, origin)),
+        }
+    }
+    (modules.into_iter().map(|(module, _)| module).collect(), shadowed)
+}
+
+fn known(value: String) -> Option<String> {
+    (!value.is_empty() && value != "unknown").then_some(value)
+}
+
+/// Kernel and hardware only change across reboots.
+const HARDWARE: Option<CachePolicy> =
+    Some(CachePolicy { ttl: Duration::from_secs(24 * 60 * 60), invalidate: Invalidate::Boot });
+
+struct TextModule {
+    id: &'static str,
+    label: &'static str,
+    detect: fn() -> Result<String, RFetchError>,
+    cache: Option<CachePolicy>,
+}
+
+impl Module for TextModule {
+    type Value = String;
+
+    fn id(&self) -> &str {
+        self.id
+    }
+
+    fn label(&self) -> &str {
+        self.label
+    }
+
+    fn cache(&self, _config: &Config) -> Option<CachePolicy> {
+        self.cache.clone()
+    }
+
+    fn collect(&self, _config: &Config) -> Result<Option<String>, RFetchError> {
+        Ok(known((self.detect)()?))
+    }
+
+    fn render(&self, value: &String, display: &DisplayManager) -> Vec<String> {
+        vec![display.format_info_line(self.label, value)]
+    }
+}
+
+struct SecurityModule;
+
+impl Module for SecurityModule {
+    type Value = SecurityInfo;
+
+    fn id(&self) -> &str {
+        "security"
+    }
+
+    fn label(&self) -> &str {
+        "Security"
+    }
+
+    fn detail(&self) -> Detail {
+        Detail::Verbose
+    }
+
+    fn collect(&self, _config: &Config) -> Result<Option<SecurityInfo>, RFetchError> {
+        Ok(Some(get_security_info()).filter(|security| !security.is_empty()))
+    }
+
+    fn render(&self, security: &SecurityInfo, display: &DisplayManager) -> Vec<String> {
+        let colors = &display.config().colors;
+        let mut parts = Vec::new();
+
+        if let Some(mode) = &security.selinux {
+            parts.push(display.colorize(&format!("SELinux {}", mode), &colors.value));
+        }
+
+        if let Some(enabled) = security.apparmor {
+            let text = if enabled { "AppArmor" } else { "AppArmor off" };
+            parts.push(display.colorize(text, &colors.value));
+        }
+
+        if let Some(mode) = &security.lockdown {
+            parts.push(display.colorize(&format!("Lockdown {}", mode), &colors.value));
+        }
+
+        if let Some(enabled) = security.secure_boot {
+            let (text, color) = if enabled {
+                ("Secure Boot on", &colors.value)
+            } else {
+                ("Secure Boot off", &colors.warning)
+            };
+            parts.push(display.colorize(text, color));
+        }
+
+        if security.reboot_required {
+            let text = match &security.newest_kernel {
+                Some(newest) if security.running_kernel.as_ref() != Some(newest) => {
+                    format!("reboot required ({} installed)", newest)
+                }
+                _ => "reboot required".to_string(),
+            };
+            parts.push(display.colorize(&text, &colors.warning));
+        }
+
+        let joined = parts.join(&display.colorize(", ", &colors.value));
+        vec![display.format_info_line_raw(self.label(), &joined)]
+    }
+}
+
+struct UptimeModule;
+
+impl Module for UptimeModule {
+    type Value = UptimeInfo;
+
+    fn id(&self) -> &str {
+        "uptime"
+    }
+
+    fn label(&self) -> &str {
+        "Uptime"
+    }
+
+    fn collect(&self, _config: &Config) -> Result<Option<UptimeInfo>, RFetchError> {
+        Ok(Some(SystemInfo::get_uptime()?))
+    }
+
+    fn render(&self, uptime: &UptimeInfo, display: &DisplayManager) -> Vec<String> {
+        let formatted = uptime.format(&display.config().uptime.format);
+        vec![display.format_info_line(self.label(), &formatted)]
+    }
+}
+
+struct PackagesModule;
+
+impl Module for PackagesModule {
+    type Value = u64;
+
+    fn id(&self) -> &str {
+        "packages"
+    }
+
+    fn label(&self) -> &str {
+        "Packages"
+    }
+
+    fn cache(&self, _config: &Config) -> Option<CachePolicy> {
+        Some(CachePolicy {
+            ttl: Duration::from_secs(60 * 60),
+            invalidate: Invalidate::Modified(package_databases()),
+        })
+    }
+
+    fn collect(&self, _config: &Config) -> Result<Option<u64>, RFetchError> {
+        Ok(Some(count_packages()).filter(|count| *count > 0))
+    }
+
+    fn render(&self, count: &u64, display: &DisplayManager) -> Vec<String> {
+        vec![display.format_info_line(self.label(), &count.to_string())]
+    }
+}
+
+struct DesktopModule;
+
+impl Module for DesktopModule {
+    type Value = String;
+
+    fn id(&self) -> &str {
+        "de"
+    }
+
+    fn label(&self) -> &str {
+        "DE"
+    }
+
+    fn collect(&self, _config: &Config) -> Result<Option<String>, RFetchError> {
+        Ok(detect_desktop().desktop_environment)
+    }
+
+    fn render(&self, name: &String, display: &DisplayManager) -> Vec<String> {
+        vec![display.format_info_line(self.label(), name)]
+    }
+}
+
+struct SessionModule;
+
+impl Module for SessionModule {
+    type Value = String;
+
+    fn id(&self) -> &str {
+        "session"
+    }
+
+    fn label(&self) -> &str {
+        "Session"
+    }
+
+    fn detail(&self) -> Detail {
+        Detail::Verbose
+    }
+
+    /// Also reported when no DE or WM is found, e.g. on a TTY or a bare Wayland compositor.
+    fn collect(&self, _config: &Config) -> Result<Option<String>, RFetchError> {
+        if cfg!(any(target_os = "macos", target_os = "windows")) {
+            return Ok(None);
+        }
+        Ok(Some(detect_session_type()))
+    }
+
+    fn render(&self, session_type: &String, display: &DisplayManager) -> Vec<String> {
+        vec![display.format_info_line(self.label(), session_type)]
+    }
+}
+
+struct WindowManagerModule;
+
+impl Module for WindowManagerModule {
+    type Value = DesktopInfo;
+
+    fn id(&self) -> &str {
+        "wm"
+    }
+
+    fn label(&self) -> &str {
+        "WM"
+    }
+
+    fn collect(&self, _config: &Config) -> Result<Option<DesktopInfo>, RFetchError> {
+        let desktop = detect_desktop();
+        Ok(desktop.window_manager.is_some().then_some(desktop))
+    }
+
+    fn render(&self, desktop: &DesktopInfo, display: &DisplayManager) -> Vec<String> {
+        let window_manager = desktop.window_manager.clone().unwrap_or_default();
+        let wm_str = if desktop.session_type.is_empty() {
+            window_manager
+        } else {
+            format!("{} ({})", window_manager, desktop.session_type)
+        };
+        vec![display.format_info_line(self.label(), &wm_str)]
+    }
+
+    fn to_json(&self, desktop: &DesktopInfo) -> serde_json::Value {
+        serde_json::json!({
+            "name": desktop.window_manager,
+            "session_type": desktop.session_type,
+        })
+    }
+}
+
+struct SoundModule;
+
+impl Module for SoundModule {
+    type Value = SoundInfo;
+
+    fn id(&self) -> &str {
+        "sound"
+    }
+
+    fn label(&self) -> &str {
+        "Sound"
+    }
+
+    fn detail(&self) -> Detail {
+        Detail::Verbose
+    }
+
+    fn collect(&self, _config: &Config) -> Result<Option<SoundInfo>, RFetchError> {
+        Ok(SystemInfo::get_sound_info().ok())
+    }
+
+    fn render(&self, sound: &SoundInfo, display: &DisplayManager) -> Vec<String> {
+        let mut lines = Vec::new();
+
+        if let Some(server) = &sound.server {
+            let mut sound_str = with_version(server, sound.server_version.clone());
+            if let Some(sink) = &sound.default_sink {
+                sound_str = format!("{} ({})", sound_str, sink);
+            }
+            lines.push(display.format_info_line(self.label(), &sound_str));
+        }
+        if !sound.cards.is_empty() {
+            lines.push(display.format_info_line("Sound Cards", &sound.cards.join(", ")));
+        }
+
+        lines
+    }
+}
+
+struct MemoryModule;
+
+impl Module for MemoryModule {
+    type Value = MemoryInfo;
+
+    fn id(&self) -> &str {
+        "memory"
+    }
+
+    fn label(&self) -> &str {
+        "Memory"
+    }
+
+    fn collect(&self, _config: &Config) -> Result<Option<MemoryInfo>, RFetchError> {
+        Ok(Some(SystemInfo::get_memory_info()?).filter(|memory| memory.total > 0))
+    }
+
+    fn render(&self, memory: &MemoryInfo, display: &DisplayManager) -> Vec<String> {
+        let memory_str = format!(
+            "{} / {} ({}%)",
+            format_bytes(memory.used),
+            format_bytes(memory.total),
+            memory.percentage as u8
+        );
+        vec![display.format_info_line(self.label(), &memory_str)]
+    }
+}
+
+struct DiskModule;
+
+impl Module for DiskModule {
+    type Value = Vec<DiskInfo>;
+
+    fn id(&self) -> &str {
+        "disk"
+    }
+
+    fn label(&self) -> &str {
+        "Disk"
+    }
+
+    fn collect(&self, _config: &Config) -> Result<Option<Vec<DiskInfo>>, RFetchError> {
+        Ok(Some(SystemInfo::get_disk_info()?).filter(|disks| !disks.is_empty()))
+    }
+
+    fn render(&self, disks: &Vec<DiskInfo>, display: &DisplayManager) -> Vec<String> {
+        disks.iter()
+            .find(|disk| disk.mount_point == "/" || disk.mount_point == "C:\\")
+            .map(|disk| {
+                let disk_str = format!(
+                    "{} / {} ({}%)",
+                    format_bytes(disk.used),
+                    format_bytes(disk.total),
+                    disk.percentage as u8
+                );
+                display.format_info_line(self.label(), &disk_str)
+            })
+            .into_iter()
+            .collect()
+    }
+}
+
+struct BatteryModule;
+
+impl Module for BatteryModule {
+    type Value = BatteryInfo;
+
+    fn id(&self) -> &str {
+        "battery"
+    }
+
+    fn label(&self) -> &str {
+        "Battery"
+    }
+
+    fn collect(&self, _config: &Config) -> Result<Option<BatteryInfo>, RFetchError> {
+        Ok(SystemInfo::get_battery_info().ok())
+    }
+
+    fn render(&self, battery: &BatteryInfo, display: &DisplayManager) -> Vec<String> {
+        let battery_str = format!("{}% ({})", battery.percentage, battery.status);
+        vec![display.format_info_line(self.label(), &battery_str)]
+    }
+}
+
+#[cfg(feature = "sensors")]
+struct SensorsModule;
+
+#[cfg(feature = "sensors")]
+impl Module for SensorsModule {
+    type Value = SensorsInfo;
+
+    fn id(&self) -> &str {
+        "sensors"
+    }
+
+    fn label(&self) -> &str {
+        "Sensors"
+    }
+
+    fn detail(&self) -> Detail {
+        Detail::Verbose
+    }
+
+    fn collect(&self, _config: &Config) -> Result<Option<SensorsInfo>, RFetchError> {
+        Ok(Some(get_sensors()).filter(|sensors| !sensors.is_empty()))
+    }
+
+    fn render(&self, sensors: &SensorsInfo, display: &DisplayManager) -> Vec<String> {
+        let config = display.config();
+        let thresholds = &config.sensors;
+        let mut parts = Vec::new();
+
+        for kind in [SensorKind::Cpu, SensorKind::Gpu, SensorKind::Nvme, SensorKind::Board] {
+            if let Some(reading) = sensors.hottest(kind) {
+                let critical = reading.critical.map_or(thresholds.critical_temp, |crit| crit.min(thresholds.critical_temp));
+                let color = if reading.celsius >= critical {
+                    &config.colors.error
+                } else if reading.celsius >= thresholds.warning_temp {
+                    &config.colors.warning
+                } else {
+                    &config.colors.value
+                };
+                let text = format!("{} {:.0}°C", kind.label(), reading.celsius);
+                parts.push(display.colorize(&text, color));
+            }
+        }
+
+        if thresholds.show_fans {
+            let rpms: Vec<String> = sensors.fans.iter()
+                .filter(|fan| fan.rpm > 0)
+                .map(|fan| fan.rpm.to_string())
+                .collect();
+            if !rpms.is_empty() {
+                let label = if rpms.len() == 1 { "Fan" } else { "Fans" };
+                let text = format!("{} {} RPM", label, rpms.join("/"));
+                parts.push(display.colorize(&text, &config.colors.value));
+            }
+        }
+
+        if parts.is_empty() {
+            return Vec::new();
+        }
+
+        let joined = parts.join(&display.colorize(", ", &config.colors.value));
+        vec![display.format_info_line_raw(self.label(), &joined)]
+    }
+}
+
+struct LocaleModule;
+
+impl Module for LocaleModule {
+    type Value = LocaleInfo;
+
+    fn id(&self) -> &str {
+        "locale"
+    }
+
+    fn label(&self) -> &str {
+        "Locale"
+    }
+
+    fn detail(&self) -> Detail {
+        Detail::Verbose
+    }
+
+    fn collect(&self, _config: &Config) -> Result<Option<LocaleInfo>, RFetchError> {
+        let locale = SystemInfo::get_locale();
+        Ok(locale.effective().is_some().then_some(locale))
+    }
+
+    fn render(&self, locale: &LocaleInfo, display: &DisplayManager) -> Vec<String> {
+        let effective = locale.effective().unwrap_or_default();
+        let mut overrides: Vec<String> = locale.overrides.iter()
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect();
+        if let (Some(lc_all), Some(lang)) = (&locale.lc_all, &locale.lang) {
+            if lc_all != lang {
+                overrides.insert(0, format!("LANG={}", lang));
+            }
+        }
+        let locale_str = if overrides.is_empty() {
+            effective.to_string()
+        } else {
+            format!("{} ({})", effective, overrides.join(", "))
+        };
+        vec![display.format_info_line(self.label(), &locale_str)]
+    }
+}
+
+#[cfg(feature = "network")]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LocalNetwork {
+    pub address: Option<String>,
+    #[serde(flatten)]
+    pub network: NetworkInfo,
+}
+
+#[cfg(feature = "network")]
+struct LocalIpModule;
+
+#[cfg(feature = "network")]
+impl Module for LocalIpModule {
+    type Value = LocalNetwork;
+
+    fn id(&self) -> &str {
+        "local_ip"
+    }
+
+    fn label(&self) -> &str {
+        "Local IP"
+    }
+
+    fn detail(&self) -> Detail {
+        Detail::Verbose
+    }
+
+    fn collect(&self, _config: &Config) -> Result<Option<LocalNetwork>, RFetchError> {
+        Ok(Some(LocalNetwork {
+            address: get_local_ip(),
+            network: get_network_info(),
+        }))
+    }
+
+    fn render(&self, local: &LocalNetwork, display: &DisplayManager) -> Vec<String> {
+        let mut lines = Vec::new();
+        let network = &local.network;
+
+        if let Some(address) = &local.address {
+            lines.push(display.format_info_line(self.label(), address));
+        }
+
+        if let Some(wifi) = &network.wifi {
+            let mut details = vec![wifi.interface.clone()];
+            if let Some(quality) = wifi.quality {
+                details.push(format!("{}%", quality));
+            }
+            if let Some(dbm) = wifi.signal_dbm {
+                details.push(format!("{} dBm", dbm));
+            }
+            let wifi_str = match &wifi.ssid {
+                Some(ssid) => format!("{} ({})", ssid, details.join(", ")),
+                None => details.join(", "),
+            };
+            lines.push(display.format_info_line("Wi-Fi", &wifi_str));
+        }
+
+        let gateways: Vec<String> = [&network.gateway, &network.gateway_v6]
+            .iter()
+            .filter_map(|gateway| gateway.as_ref())
+            .map(|gateway| format!("{} ({})", gateway.address, gateway.interface))
+            .collect();
+        if !gateways.is_empty() {
+            lines.push(display.format_info_line("Gateway", &gateways.join(", ")));
+        }
+
+        if !network.dns.is_empty() {
+            let dns_str = match &network.dns_resolver {
+                Some(resolver) => format!("{} (via {})", network.dns.join(", "), resolver),
+                None => network.dns.join(", "),
+            };
+            lines.push(display.format_info_line("DNS", &dns_str));
+        }
+
+        lines
+    }
+}
+
+#[cfg(feature = "users")]
+struct UsersModule;
+
+#[cfg(feature = "users")]
+impl Module for UsersModule {
+    type Value = UsersInfo;
+
+    fn id(&self) -> &str {
+        "users"
+    }
+
+    fn label(&self) -> &str {
+        "Users"
+    }
+
+    fn detail(&self) -> Detail {
+        Detail::Verbose
+    }
+
+    fn collect(&self, _config: &Config) -> Result<Option<UsersInfo>, RFetchError> {
+        let users = get_users_info();
+        Ok((!users.users.is_empty() || users.last_login.is_some()).then_some(users))
+    }
+
+    fn render(&self, users: &UsersInfo, display: &DisplayManager) -> Vec<String> {
+        let mut lines = Vec::new();
+
+        if !users.users.is_empty() {
+            lines.push(display.format_info_line(self.label(), &format_compact(&users.users)));
+        }
+
+        if let Some(last) = &users.last_login {
+            let mut details = vec![last.tty.clone()];
+            if let Some(host) = &last.host {
+                details.push(format!("from {}", host));
+            }
+            let last_str = match &last.login_time {
+                Some(time) => format!("{} ({})", time.format("%Y-%m-%d %H:%M"), details.join(" ")),
+                None => details.join(" "),
+            };
+            lines.push(display.format_info_line("Last Login", &last_str));
+        }
+
+        lines
+    }
+}
+
+#[cfg(feature = "toolchains")]
+struct ToolchainsModule;
+
+#[cfg(feature = "toolchains")]
+impl Module for ToolchainsModule {
+    type Value = Vec<ToolchainInfo>;
+
+    fn id(&self) -> &str {
+        "toolchains"
+    }
+
+    fn label(&self) -> &str {
+        "Toolchains"
+    }
+
+    fn detail(&self) -> Detail {
+        Detail::OptIn
+    }
+
+    /// Versions only change when a probed binary is replaced; `toolchains.cache_ttl` covers
+    /// proxies such as rustup or pyenv shims, which stay the same file across switches.
+    fn cache(&self, config: &Config) -> Option<CachePolicy> {
+        Some(CachePolicy {
+            ttl: Duration::from_secs(config.toolchains.cache_ttl),
+            invalidate: Invalidate::Modified(find_probes(&config.toolchains).into_iter().map(|(_, path)| path).collect()),
+        })
+    }
+
+    fn collect(&self, config: &Config) -> Result<Option<Vec<ToolchainInfo>>, RFetchError> {
+        Ok(Some(get_toolchains(&config.toolchains)).filter(|toolchains| !toolchains.is_empty()))
+    }
+
+    fn render(&self, toolchains: &Vec<ToolchainInfo>, display: &DisplayManager) -> Vec<String> {
+        let config = display.config();
+        let section = display.theme().and_then(|theme| {
+            theme.sections.iter().find(|section| section.name == "toolchains")
+        });
+
+        if section.map(|section| !section.visible).unwrap_or(false) {
+            return Vec::new();
+        }
+
+        let ordered: Vec<&ToolchainInfo> = match section {
+            Some(section) if !section.items.is_empty() => section.items.iter()
+                .filter_map(|id| toolchains.iter().find(|toolchain| &toolchain.id == id))
+                .collect(),
+            _ => toolchains.iter().collect(),
+        };
+
+        if ordered.is_empty() {
+            return Vec::new();
+        }
+
+        let title = section
+            .and_then(|section| section.title.clone())
+            .unwrap_or_else(|| config.toolchains.title.clone());
+
+        if config.toolchains.render == "section" {
+            let mut lines = vec![display.colorize(&title, &config.colors.subtitle)];
+            for toolchain in ordered {
+                lines.push(display.format_info_line(&format!("  {}", toolchain.name), &toolchain.version));
+            }
+            lines
+        } else {
+            let summary: Vec<String> = ordered.iter()
+                .map(|toolchain| format!("{} {}", toolchain.name, toolchain.version))
+                .collect();
+            vec![display.format_info_line(&title, &summary.join(", "))]
+        }
+    }
+}
+
+struct DateModule;
+
+impl Module for DateModule {
+    type Value = DateInfo;
+
+    fn id(&self) -> &str {
+        "date"
+    }
+
+    fn label(&self) -> &str {
+        "Date"
+    }
+
+    fn collect(&self, config: &Config) -> Result<Option<DateInfo>, RFetchError> {
+        Ok(Some(get_date_info(&config.date)))
+    }
+
+    fn render(&self, date: &DateInfo, display: &DisplayManager) -> Vec<String> {
+        let date_config = &display.config().date;
+        let mut extras = Vec::new();
+
+        if date_config.show_utc_offset {
+            extras.push(format!("UTC{}", date.utc_offset));
+        }
+        if date_config.show_week {
+            extras.push(format!("W{:02}", date.iso_week));
+        }
+
+        let mut date_str = date.formatted.clone();
+        if date_config.show_timezone {
+            if let Some(timezone) = &date.timezone {
+                date_str = format!("{} {}", date_str, timezone);
+            }
+        }
+        if !extras.is_empty() {
+            date_str = format!("{} ({})", date_str, extras.join(", "));
+        }
+
+        vec![display.format_info_line(self.label(), &date_str)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_module_ids_are_unique() {
+        let ids: HashSet<&str> = MODULES.iter().map(|module| module.id()).collect();
+        assert_eq!(ids.len(), MODULES.len());
+        assert!(find("cpu").is_some());
+        assert!(find("nope").is_none());
+    }
+
+    #[test]
+    fn test_info_toggles_fall_back_to_registry_defaults() {
+        let info: crate::config::InfoConfig = toml::from_str("show_cpu = false\nshow_theme = true\n").unwrap();
+        let enabled = |id: &str| info.is_enabled(find(id).unwrap().as_ref());
+        assert!(!enabled("cpu"));
+        assert!(enabled("memory"));
+        assert!(!enabled("locale"));
+    }
+
+    #[cfg(feature = "custom")]
+    #[test]
+    fn test_shadowed_extras_are_reported() {
+        let custom = |id: &str| crate::config::CustomModuleConfig {
+            id: id.to_string(),
+            label: id.to_string(),
+            command: Some("true".to_string()),
+            file: None,
+            regex: None,
+            timeout_ms: 1000,
+            cache_ttl: 0,
+        };
+        let mut config = Config::default();
+        config.plugins.enabled = false;
+        config.custom = vec![custom("cpu"), custom("weather"), custom("weather")];
+
+        let ids: Vec<String> = registry(&config).iter().map(|module| module.id().to_string()).collect();
+        assert_eq!(ids.iter().filter(|id| *id == "cpu" || *id == "weather").count(), 2);
+        assert_eq!(
+            shadowed(&config),
+            vec![
+                Shadowed { id: "cpu".to_string(), origin: Origin::Custom, by: Origin::BuiltIn },
+                Shadowed { id: "weather".to_string(), origin: Origin::Custom, by: Origin::Custom },
+            ]
+        );
+        assert_eq!(shadowed(&config)[0].to_string(), "custom module 'cpu' is ignored: a built-in module already uses that id");
+    }
+
+    #[test]
+    fn test_known_filters_placeholders() {
+        assert_eq!(known("bash 5.2".to_string()), Some("bash 5.2".to_string()));
+        assert_eq!(known("unknown".to_string()), None);
+        assert_eq!(known(String::new()), None);
+    }
+}
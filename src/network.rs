@@ -0,0 +1,292 @@
+use serde::{Deserialize, Serialize};
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use crate::sysroot;
+use crate::trace::trace;
+
+const RESOLVED_STUB: &str = "127.0.0.53";
+const RTF_GATEWAY: u32 = 0x2;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NetworkInfo {
+    pub wifi: Option<WifiInfo>,
+    pub gateway: Option<Gateway>,
+    pub gateway_v6: Option<Gateway>,
+    pub dns: Vec<String>,
+    pub dns_resolver: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WifiInfo {
+    pub interface: String,
+    pub ssid: Option<String>,
+    pub quality: Option<u8>,
+    pub signal_dbm: Option<i32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Gateway {
+    pub address: String,
+    pub interface: String,
+}
+
+pub fn get_network_info() -> NetworkInfo {
+    let (dns, dns_resolver) = get_dns_servers();
+
+    NetworkInfo {
+        wifi: get_wifi_info(),
+        gateway: sysroot::read_to_string("/proc/net/route")
+            .ok()
+            .and_then(|content| parse_default_route(&content)),
+        gateway_v6: sysroot::read_to_string("/proc/net/ipv6_route")
+            .ok()
+            .and_then(|content| parse_default_route_v6(&content)),
+        dns,
+        dns_resolver,
+    }
+}
+
+/// The IPv4 address on the default route's subnet, which is what `hostname -I` lists first.
+pub fn get_local_ip() -> Option<String> {
+    let addresses = sysroot::read_to_string("/proc/net/fib_trie")
+        .map(|content| parse_local_addresses(&content))
+        .unwrap_or_default();
+    let routes = sysroot::read_to_string("/proc/net/route").unwrap_or_default();
+
+    let preferred = parse_default_route(&routes).and_then(|gateway| {
+        parse_routes(&routes)
+            .into_iter()
+            .filter(|route| route.interface == gateway.interface && route.mask != 0)
+            .find_map(|route| addresses.iter().find(|address| u32::from(**address) & route.mask == route.destination))
+            .copied()
+    });
+
+    match preferred {
+        Some(address) => trace!("{} is on the default route's subnet", address),
+        None => trace!("no local address on the default route's subnet, using the first of {:?}", addresses),
+    }
+    preferred.or_else(|| addresses.first().copied()).map(|address| address.to_string())
+}
+
+/// Non-loopback addresses the kernel treats as local: the `/32 host LOCAL` leaves of the trie.
+pub fn parse_local_addresses(content: &str) -> Vec<Ipv4Addr> {
+    let mut addresses = Vec::new();
+    let mut leaf: Option<Ipv4Addr> = None;
+
+    for line in content.lines() {
+        let line = line.trim_start();
+        if let Some(address) = line.strip_prefix("|-- ") {
+            leaf = address.trim().parse().ok();
+        } else if line.starts_with("/32 host LOCAL") {
+            if let Some(address) = leaf.filter(|address| !address.is_loopback() && !addresses.contains(address)) {
+                addresses.push(address);
+            }
+        }
+    }
+
+    addresses
+}
+
+struct Route {
+    interface: String,
+    destination: u32,
+    mask: u32,
+}
+
+/// `/proc/net/route` stores addresses as little-endian hex; these are converted to host order.
+fn parse_routes(content: &str) -> Vec<Route> {
+    let address = |hex: &str| u32::from_str_radix(hex, 16).ok().map(|raw| u32::from(Ipv4Addr::from(raw.to_le_bytes())));
+
+    content
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            Some(Route {
+                interface: fields.first()?.to_string(),
+                destination: address(fields.get(1)?)?,
+                mask: address(fields.get(7)?)?,
+            })
+        })
+        .collect()
+}
+
+fn get_wifi_info() -> Option<WifiInfo> {
+    let content = sysroot::read_to_string("/proc/net/wireless").ok()?;
+    let mut wifi = parse_wireless(&content)
+        .into_iter()
+        .find(|wifi| is_interface_up(&wifi.interface))?;
+
+    wifi.ssid = get_ssid(&wifi.interface);
+    Some(wifi)
+}
+
+fn is_interface_up(interface: &str) -> bool {
+    sysroot::read_to_string(format!("/sys/class/net/{}/operstate", interface))
+        .map(|state| state.trim() != "down")
+        .unwrap_or(true)
+}
+
+fn get_ssid(interface: &str) -> Option<String> {
+    // There is no procfs/sysfs view of the SSID; iw and iwgetid talk to nl80211 directly.
+    if let Ok(output) = sysroot::run("iw", &["dev", interface, "link"]) {
+        let output = String::from_utf8_lossy(&output.stdout);
+        for line in output.lines() {
+            if let Some(ssid) = line.trim().strip_prefix("SSID:") {
+                return Some(ssid.trim().to_string());
+            }
+        }
+    }
+
+    if let Ok(output) = sysroot::run("iwgetid", &[interface, "-r"]) {
+        let ssid = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        if output.status.success() && !ssid.is_empty() {
+            return Some(ssid);
+        }
+    }
+
+    None
+}
+
+pub fn parse_wireless(content: &str) -> Vec<WifiInfo> {
+    content
+        .lines()
+        .skip(2)
+        .filter_map(|line| {
+            let (interface, rest) = line.split_once(':')?;
+            let mut fields = rest.split_whitespace();
+            let _status = fields.next()?;
+            let link: f64 = fields.next()?.trim_end_matches('.').parse().ok()?;
+            let level: f64 = fields.next()?.trim_end_matches('.').parse().ok()?;
+
+            Some(WifiInfo {
+                interface: interface.trim().to_string(),
+                ssid: None,
+                quality: Some(((link / 70.0) * 100.0).clamp(0.0, 100.0) as u8),
+                signal_dbm: (level < 0.0).then_some(level as i32),
+            })
+        })
+        .collect()
+}
+
+pub fn parse_default_route(content: &str) -> Option<Gateway> {
+    content.lines().skip(1).find_map(|line| {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() < 4 || fields[1] != "00000000" {
+            return None;
+        }
+
+        let flags = u32::from_str_radix(fields[3], 16).ok()?;
+        if flags & RTF_GATEWAY == 0 {
+            return None;
+        }
+
+        let gateway = u32::from_str_radix(fields[2], 16).ok()?;
+        Some(Gateway {
+            address: Ipv4Addr::from(gateway.to_le_bytes()).to_string(),
+            interface: fields[0].to_string(),
+        })
+    })
+}
+
+pub fn parse_default_route_v6(content: &str) -> Option<Gateway> {
+    content.lines().find_map(|line| {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() < 10 || fields[1] != "00" || fields[0].chars().any(|c| c != '0') {
+            return None;
+        }
+
+        let next_hop = u128::from_str_radix(fields[4], 16).ok()?;
+        if next_hop == 0 {
+            return None;
+        }
+
+        Some(Gateway {
+            address: Ipv6Addr::from(next_hop).to_string(),
+            interface: fields[9].to_string(),
+        })
+    })
+}
+
+fn get_dns_servers() -> (Vec<String>, Option<String>) {
+    let servers = sysroot::read_to_string("/etc/resolv.conf")
+        .map(|content| parse_nameservers(&content))
+        .unwrap_or_default();
+
+    if servers.iter().any(|server| server == RESOLVED_STUB) {
+        if let Ok(content) = sysroot::read_to_string("/run/systemd/resolve/resolv.conf") {
+            let upstream = parse_nameservers(&content);
+            if !upstream.is_empty() {
+                return (upstream, Some("systemd-resolved".to_string()));
+            }
+        }
+        return (servers, Some("systemd-resolved".to_string()));
+    }
+
+    (servers, None)
+}
+
+pub fn parse_nameservers(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            match fields.next() {
+                Some("nameserver") => fields.next().map(|s| s.to_string()),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_default_route() {
+        let content = "Iface\tDestination\tGateway \tFlags\tRefCnt\tUse\tMetric\tMask\n\
+                       wlan0\t00000000\t0101A8C0\t0003\t0\t0\t600\t00000000\n\
+                       wlan0\t0001A8C0\t00000000\t0001\t0\t0\t600\t00FFFFFF\n";
+        let gateway = parse_default_route(content).unwrap();
+        assert_eq!(gateway.address, "192.168.1.1");
+        assert_eq!(gateway.interface, "wlan0");
+    }
+
+    #[test]
+    fn test_local_address_follows_default_route() {
+        let fib_trie = "Main:\n  +-- 0.0.0.0/0 3 0 5\n     |-- 0.0.0.0\n        /0 universe UNICAST\n\
+                        \x20    |-- 127.0.0.1\n        /32 host LOCAL\n\
+                        \x20    |-- 172.17.0.1\n        /32 host LOCAL\n\
+                        \x20    |-- 192.168.1.42\n        /32 host LOCAL\n\
+                        \x20    |-- 192.168.1.255\n        /32 link BROADCAST\n";
+        assert_eq!(
+            parse_local_addresses(fib_trie),
+            vec![Ipv4Addr::new(172, 17, 0, 1), Ipv4Addr::new(192, 168, 1, 42)]
+        );
+
+        let routes = "Iface\tDestination\tGateway \tFlags\tRefCnt\tUse\tMetric\tMask\n\
+                      wlan0\t00000000\t0101A8C0\t0003\t0\t0\t600\t00000000\n\
+                      docker0\t000011AC\t00000000\t0001\t0\t0\t0\t0000FFFF\n\
+                      wlan0\t0001A8C0\t00000000\t0001\t0\t0\t600\t00FFFFFF\n";
+        let wlan = parse_routes(routes).into_iter().find(|route| route.interface == "wlan0" && route.mask != 0).unwrap();
+        assert_eq!(Ipv4Addr::from(wlan.destination), Ipv4Addr::new(192, 168, 1, 0));
+    }
+
+    #[test]
+    fn test_parse_wireless() {
+        let content = "Inter-| sta-|   Quality        |   Discarded packets\n \
+                       face | tus | link level noise |  nwid  crypt   frag\n \
+                       wlan0: 0000   54.  -56.  -256        0      0      0\n";
+        let wifi = parse_wireless(content);
+        assert_eq!(wifi[0].interface, "wlan0");
+        assert_eq!(wifi[0].quality, Some(77));
+        assert_eq!(wifi[0].signal_dbm, Some(-56));
+    }
+
+    #[test]
+    fn test_parse_nameservers() {
+        let content = "# generated\nnameserver 127.0.0.53\noptions edns0\nnameserver ::1\n";
+        assert_eq!(parse_nameservers(content), vec!["127.0.0.53", "::1"]);
+    }
+}
@@ -0,0 +1,118 @@
+//! PCI devices read from sysfs and named through the `pci.ids` database, the same data `lspci`
+//! prints, without needing pciutils installed.
+
+use std::fs;
+use std::path::Path;
+
+use crate::sysroot;
+
+const DEVICES_DIR: &str = "/sys/bus/pci/devices";
+/// Where distributions install the `pci.ids` database, in lookup order.
+pub const PCI_IDS: &[&str] = &["/usr/share/hwdata/pci.ids", "/usr/share/misc/pci.ids", "/usr/share/pci.ids"];
+
+/// Class codes (base class and subclass) of VGA-compatible and 3D controllers.
+pub const VGA_CONTROLLER: u16 = 0x0300;
+pub const CONTROLLER_3D: u16 = 0x0302;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PciDevice {
+    pub slot: String,
+    pub class: u16,
+    pub vendor: u16,
+    pub device: u16,
+    pub revision: Option<u8>,
+}
+
+/// Every device under `/sys/bus/pci/devices`, ordered by slot.
+pub fn devices() -> Vec<PciDevice> {
+    let mut devices: Vec<PciDevice> = match sysroot::read_dir(DEVICES_DIR) {
+        Ok(entries) => entries.filter_map(|entry| entry.ok()).filter_map(|entry| read_device(&entry.path())).collect(),
+        Err(_) => Vec::new(),
+    };
+    devices.sort_by(|a, b| a.slot.cmp(&b.slot));
+    devices
+}
+
+fn read_device(dir: &Path) -> Option<PciDevice> {
+    let hex = |name: &str| {
+        let value = fs::read_to_string(dir.join(name)).ok()?;
+        u32::from_str_radix(value.trim().trim_start_matches("0x"), 16).ok()
+    };
+
+    Some(PciDevice {
+        slot: dir.file_name()?.to_string_lossy().to_string(),
+        class: (hex("class")? >> 8) as u16,
+        vendor: hex("vendor")? as u16,
+        device: hex("device")? as u16,
+        revision: hex("revision").map(|revision| revision as u8),
+    })
+}
+
+impl PciDevice {
+    /// The description `lspci` prints after the class, e.g.
+    /// `Intel Corporation TigerLake-LP GT2 [Iris Xe Graphics] (rev 01)`.
+    pub fn describe(&self) -> String {
+        let ids = PCI_IDS.iter().find_map(|path| sysroot::read_to_string(path).ok()).unwrap_or_default();
+        let (vendor, device) = lookup(&ids, self.vendor, self.device);
+
+        let mut description = match (vendor, device) {
+            (Some(vendor), Some(device)) => format!("{} {}", vendor, device),
+            (Some(vendor), None) => format!("{} Device {:04x}", vendor, self.device),
+            _ => format!("Device {:04x}:{:04x}", self.vendor, self.device),
+        };
+        if let Some(revision) = self.revision.filter(|revision| *revision != 0) {
+            description.push_str(&format!(" (rev {:02x})", revision));
+        }
+        description
+    }
+}
+
+/// Finds vendor and device names in `pci.ids` content. Vendor lines start at column 0, their
+/// devices follow indented by one tab.
+pub fn lookup(ids: &str, vendor: u16, device: u16) -> (Option<String>, Option<String>) {
+    let vendor_id = format!("{:04x}", vendor);
+    let device_id = format!("{:04x}", device);
+    let mut vendor_name = None;
+
+    for line in ids.lines() {
+        if line.starts_with('#') || line.is_empty() {
+            continue;
+        }
+        if !line.starts_with('\t') {
+            if vendor_name.is_some() {
+                break;
+            }
+            if let Some(name) = line.strip_prefix(vendor_id.as_str()) {
+                vendor_name = Some(name.trim().to_string());
+            }
+        } else if vendor_name.is_some() && !line.starts_with("\t\t") {
+            if let Some(name) = line[1..].strip_prefix(device_id.as_str()) {
+                return (vendor_name, Some(name.trim().to_string()));
+            }
+        }
+    }
+
+    (vendor_name, None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookup_pci_ids() {
+        let ids = "# comment\n\
+                   10de  NVIDIA Corporation\n\
+                   \t2786  AD104 [GeForce RTX 4070]\n\
+                   \t\t1043 8910  Dual RTX 4070\n\
+                   8086  Intel Corporation\n\
+                   \t9a49  TigerLake-LP GT2 [Iris Xe Graphics]\n";
+
+        assert_eq!(
+            lookup(ids, 0x8086, 0x9a49),
+            (Some("Intel Corporation".to_string()), Some("TigerLake-LP GT2 [Iris Xe Graphics]".to_string()))
+        );
+        assert_eq!(lookup(ids, 0x10de, 0x1043), (Some("NVIDIA Corporation".to_string()), None));
+        assert_eq!(lookup(ids, 0x1002, 0x73bf), (None, None));
+    }
+}
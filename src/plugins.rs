@@ -0,0 +1,204 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::config::Config;
+use crate::display::DisplayManager;
+use crate::error::RFetchError;
+use crate::modules::{self, Module, ModuleHandle, Registered};
+use crate::themes::AdvancedColor;
+use crate::utils::{is_executable, output_with_input};
+
+pub const PROTOCOL_VERSION: u32 = 1;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PluginRequest {
+    pub protocol: u32,
+    pub rfetch_version: String,
+    pub module: String,
+    pub theme: Option<String>,
+    pub modules: Vec<String>,
+    pub config: Config,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PluginResponse {
+    pub protocol: u32,
+    #[serde(default)]
+    pub fields: Vec<PluginField>,
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginField {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+    pub label: String,
+    pub value: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+}
+
+pub struct PluginModule {
+    id: String,
+    path: PathBuf,
+    timeout_ms: u64,
+}
+
+pub fn plugin_dir(config: &Config) -> Option<PathBuf> {
+    match &config.plugins.dir {
+        Some(dir) => Some(PathBuf::from(dir)),
+        None => dirs::config_dir().map(|dir| dir.join("rfetch").join("plugins")),
+    }
+}
+
+pub fn plugin_modules(config: &Config) -> Vec<ModuleHandle> {
+    if !config.plugins.enabled {
+        return Vec::new();
+    }
+
+    plugin_dir(config)
+        .map(|dir| discover(&dir))
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(id, path)| {
+            let plugin = PluginModule { id, path, timeout_ms: config.plugins.timeout_ms };
+            Arc::new(Registered(plugin)) as ModuleHandle
+        })
+        .collect()
+}
+
+fn discover(dir: &Path) -> Vec<(String, PathBuf)> {
+    let mut plugins: Vec<(String, PathBuf)> = fs::read_dir(dir)
+        .map(|entries| entries.filter_map(|entry| entry.ok()).map(|entry| entry.path()).collect::<Vec<_>>())
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|path| is_executable(path))
+        .filter_map(|path| {
+            let id = path.file_stem()?.to_str()?.to_string();
+            (!id.starts_with('.')).then_some((id, path))
+        })
+        .collect();
+
+    plugins.sort();
+    plugins
+}
+
+impl Module for PluginModule {
+    type Value = Vec<PluginField>;
+
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn label(&self) -> &str {
+        &self.id
+    }
+
+    fn timeout(&self) -> Option<Duration> {
+        Some(Duration::from_millis(self.timeout_ms + 100))
+    }
+
+    fn collect(&self, config: &Config) -> Result<Option<Vec<PluginField>>, RFetchError> {
+        let request = PluginRequest {
+            protocol: PROTOCOL_VERSION,
+            rfetch_version: env!("CARGO_PKG_VERSION").to_string(),
+            module: self.id.clone(),
+            theme: config.display.theme.clone(),
+            modules: modules::registry(config)
+                .iter()
+                .filter(|module| config.info.is_enabled(module.as_ref()))
+                .map(|module| module.id().to_string())
+                .collect(),
+            config: config.clone(),
+        };
+        let input = serde_json::to_vec(&request)?;
+
+        let output = output_with_input(
+            &mut Command::new(&self.path),
+            Some(&input),
+            Duration::from_millis(self.timeout_ms),
+        )
+        .ok_or_else(|| RFetchError::system_info(format!("plugin '{}' failed to start or timed out", self.id)))?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let reason = stderr.lines().next().unwrap_or("no output");
+            return Err(RFetchError::system_info(format!(
+                "plugin '{}' exited with {}: {}",
+                self.id, output.status, reason
+            )));
+        }
+
+        parse_response(&self.id, &output.stdout)
+    }
+
+    fn render(&self, fields: &Vec<PluginField>, display: &DisplayManager) -> Vec<String> {
+        fields
+            .iter()
+            .map(|field| match &field.color {
+                Some(color) => {
+                    let value = display.colorize(&field.value, &AdvancedColor::from(color.as_str()));
+                    display.format_info_line_raw(&field.label, &value)
+                }
+                None => display.format_info_line(&field.label, &field.value),
+            })
+            .collect()
+    }
+}
+
+pub fn parse_response(id: &str, stdout: &[u8]) -> Result<Option<Vec<PluginField>>, RFetchError> {
+    let response: PluginResponse = serde_json::from_slice(stdout)
+        .map_err(|e| RFetchError::system_info(format!("plugin '{}' returned invalid JSON: {}", id, e)))?;
+
+    if response.protocol == 0 || response.protocol > PROTOCOL_VERSION {
+        return Err(RFetchError::system_info(format!(
+            "plugin '{}' speaks protocol {}, rfetch supports up to {}",
+            id, response.protocol, PROTOCOL_VERSION
+        )));
+    }
+
+    if let Some(error) = response.error.filter(|error| !error.is_empty()) {
+        return Err(RFetchError::system_info(format!("plugin '{}': {}", id, error)));
+    }
+
+    Ok(Some(response.fields).filter(|fields| !fields.is_empty()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_response() {
+        let fields = parse_response("weather", br#"{"protocol": 1, "fields": [{"label": "Weather", "value": "21C"}]}"#)
+            .unwrap()
+            .unwrap();
+        assert_eq!(fields[0].value, "21C");
+
+        assert!(parse_response("weather", br#"{"protocol": 1, "fields": []}"#).unwrap().is_none());
+        assert!(parse_response("weather", br#"{"protocol": 2, "fields": []}"#).is_err());
+        assert!(parse_response("weather", br#"{"protocol": 1, "error": "offline"}"#).is_err());
+        assert!(parse_response("weather", b"not json").is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_sample_plugin() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("plugins");
+        let mut config = Config::default();
+        config.plugins.dir = Some(dir.to_string_lossy().to_string());
+
+        let plugins = plugin_modules(&config);
+        let sample = plugins.iter().find(|plugin| plugin.id() == "sample").expect("sample plugin");
+
+        let value = sample.collect(&config).unwrap().expect("sample plugin fields");
+        let json = sample.to_json(&value);
+        assert_eq!(json[0]["label"], "Sample");
+        assert_eq!(json[0]["value"], "protocol 1, module sample");
+    }
+}
@@ -0,0 +1,202 @@
+use std::path::{Path, PathBuf};
+
+#[cfg(unix)]
+use crate::sysroot;
+use crate::trace::{self, trace};
+
+const MAX_CHAIN_DEPTH: usize = 64;
+
+#[derive(Debug, Clone)]
+pub struct ProcessInfo {
+    pub pid: u32,
+    pub ppid: u32,
+    pub comm: String,
+    pub exe: Option<PathBuf>,
+}
+
+impl ProcessInfo {
+    pub fn name(&self) -> String {
+        let comm = self.comm.trim_start_matches('-');
+        let comm = Path::new(comm)
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or(comm);
+
+        // The kernel truncates comm to 15 bytes, so prefer the executable
+        // name when it is the longer form of the same word.
+        if comm.len() >= 15 {
+            if let Some(exe_name) = self.exe_name() {
+                if exe_name.starts_with(comm) {
+                    return exe_name;
+                }
+            }
+        }
+
+        comm.to_string()
+    }
+
+    pub fn exe_name(&self) -> Option<String> {
+        self.exe
+            .as_ref()
+            .and_then(|exe| exe.file_name())
+            .and_then(|name| name.to_str())
+            .map(|name| name.to_string())
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub fn read_process(pid: u32) -> Option<ProcessInfo> {
+    let stat = sysroot::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    let (comm, ppid) = parse_stat(&stat)?;
+    let exe = sysroot::read_link(format!("/proc/{}/exe", pid))
+        .ok()
+        .map(|path| {
+            let path = path.to_string_lossy();
+            PathBuf::from(path.trim_end_matches(" (deleted)"))
+        });
+
+    Some(ProcessInfo { pid, ppid, comm, exe })
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+pub fn read_process(pid: u32) -> Option<ProcessInfo> {
+    let output = sysroot::run("ps", &["-o", "ppid=,comm=", "-p", &pid.to_string()]).ok()?;
+    let output = String::from_utf8_lossy(&output.stdout);
+    let line = output.lines().next()?.trim();
+    let (ppid, comm) = line.split_once(char::is_whitespace)?;
+    let comm = comm.trim();
+
+    Some(ProcessInfo {
+        pid,
+        ppid: ppid.trim().parse().ok()?,
+        comm: comm.to_string(),
+        exe: comm.starts_with('/').then(|| PathBuf::from(comm)),
+    })
+}
+
+#[cfg(windows)]
+pub fn read_process(_pid: u32) -> Option<ProcessInfo> {
+    None
+}
+
+pub fn parse_stat(stat: &str) -> Option<(String, u32)> {
+    // comm may itself contain spaces and parentheses, so split on the last ')'.
+    let open = stat.find('(')?;
+    let close = stat.rfind(')')?;
+    let comm = stat.get(open + 1..close)?.to_string();
+
+    let mut fields = stat[close + 1..].split_whitespace();
+    let _state = fields.next()?;
+    let ppid = fields.next()?.parse().ok()?;
+
+    Some((comm, ppid))
+}
+
+#[cfg(target_os = "linux")]
+pub fn list_processes() -> Vec<ProcessInfo> {
+    let entries = match sysroot::read_dir("/proc") {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+
+    let processes: Vec<ProcessInfo> = {
+        let _mute = trace::mute();
+        entries
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| entry.file_name().to_str().and_then(|name| name.parse().ok()))
+            .filter_map(read_process)
+            .collect()
+    };
+    trace!("found {} processes in /proc", processes.len());
+    processes
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+pub fn list_processes() -> Vec<ProcessInfo> {
+    let output = match sysroot::run("ps", &["-A", "-o", "pid=,ppid=,comm="]) {
+        Ok(output) => output,
+        Err(_) => return Vec::new(),
+    };
+
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let pid = fields.next()?.parse().ok()?;
+            let ppid = fields.next()?.parse().ok()?;
+            let comm = fields.collect::<Vec<_>>().join(" ");
+            Some(ProcessInfo {
+                pid,
+                ppid,
+                exe: comm.starts_with('/').then(|| PathBuf::from(&comm)),
+                comm,
+            })
+        })
+        .collect()
+}
+
+#[cfg(windows)]
+pub fn list_processes() -> Vec<ProcessInfo> {
+    Vec::new()
+}
+
+pub fn parent_chain() -> Vec<ProcessInfo> {
+    let chain = {
+        let _mute = trace::mute();
+        walk_parents()
+    };
+    if chain.is_empty() {
+        trace!("no parent processes found");
+    } else {
+        trace!("parent processes: {}", chain.iter().map(|p| p.name()).collect::<Vec<_>>().join(" <- "));
+    }
+    chain
+}
+
+fn walk_parents() -> Vec<ProcessInfo> {
+    let mut chain = Vec::new();
+    let mut pid = match read_process(std::process::id()) {
+        Some(current) => current.ppid,
+        None => return chain,
+    };
+
+    while pid > 1 && chain.len() < MAX_CHAIN_DEPTH {
+        match read_process(pid) {
+            Some(process) => {
+                pid = process.ppid;
+                chain.push(process);
+            }
+            None => break,
+        }
+    }
+
+    chain
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_stat() {
+        let stat = "1234 (zsh) S 1200 1234 1234 34816 5678 4194304";
+        assert_eq!(parse_stat(stat), Some(("zsh".to_string(), 1200)));
+    }
+
+    #[test]
+    fn test_parse_stat_with_parens_in_comm() {
+        let stat = "42 (tmux: server) (x) S 1 42 42 0 -1 4194368";
+        assert_eq!(parse_stat(stat), Some(("tmux: server) (x".to_string(), 1)));
+    }
+
+    #[test]
+    fn test_name_prefers_untruncated_exe() {
+        let process = ProcessInfo {
+            pid: 10,
+            ppid: 1,
+            comm: "gnome-terminal-".to_string(),
+            exe: Some(PathBuf::from("/usr/libexec/gnome-terminal-server")),
+        };
+        assert_eq!(process.name(), "gnome-terminal-server");
+    }
+}
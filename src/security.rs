@@ -0,0 +1,186 @@
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::fs;
+use std::path::Path;
+
+use crate::sysroot;
+
+const SECURE_BOOT_VAR: &str =
+    "/sys/firmware/efi/efivars/SecureBoot-8be4df61-93ca-11d2-aa0d-00e098032b8c";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SecurityInfo {
+    pub selinux: Option<String>,
+    pub apparmor: Option<bool>,
+    pub lockdown: Option<String>,
+    pub secure_boot: Option<bool>,
+    pub running_kernel: Option<String>,
+    pub newest_kernel: Option<String>,
+    pub reboot_required: bool,
+}
+
+impl SecurityInfo {
+    pub fn is_empty(&self) -> bool {
+        self.selinux.is_none()
+            && self.apparmor.is_none()
+            && self.lockdown.is_none()
+            && self.secure_boot.is_none()
+            && !self.reboot_required
+    }
+}
+
+pub fn get_security_info() -> SecurityInfo {
+    let running_kernel = sysroot::read_to_string("/proc/sys/kernel/osrelease")
+        .ok()
+        .map(|s| s.trim().to_string());
+    let installed = installed_kernels(&sysroot::path("/lib/modules"));
+    let newest_kernel = running_kernel
+        .as_deref()
+        .and_then(|running| newest_of_flavor(running, &installed))
+        .cloned();
+
+    let kernel_mismatch = match &running_kernel {
+        Some(running) => kernel_outdated(running, &installed),
+        None => false,
+    };
+
+    SecurityInfo {
+        selinux: get_selinux_mode(),
+        apparmor: get_apparmor_status(),
+        lockdown: get_lockdown_mode(),
+        secure_boot: get_secure_boot_state(),
+        running_kernel,
+        newest_kernel,
+        reboot_required: kernel_mismatch || sysroot::exists("/var/run/reboot-required"),
+    }
+}
+
+fn get_selinux_mode() -> Option<String> {
+    let enforce = sysroot::read_to_string("/sys/fs/selinux/enforce").ok()?;
+    match enforce.trim() {
+        "1" => Some("enforcing".to_string()),
+        "0" => Some("permissive".to_string()),
+        _ => None,
+    }
+}
+
+fn get_apparmor_status() -> Option<bool> {
+    let enabled = sysroot::read_to_string("/sys/module/apparmor/parameters/enabled").ok()?;
+    Some(enabled.trim().eq_ignore_ascii_case("y"))
+}
+
+fn get_lockdown_mode() -> Option<String> {
+    let content = sysroot::read_to_string("/sys/kernel/security/lockdown").ok()?;
+    parse_lockdown(&content)
+}
+
+fn parse_lockdown(content: &str) -> Option<String> {
+    let start = content.find('[')?;
+    let end = content[start..].find(']')? + start;
+    Some(content[start + 1..end].to_string())
+}
+
+fn get_secure_boot_state() -> Option<bool> {
+    if !sysroot::exists("/sys/firmware/efi") {
+        return None;
+    }
+
+    // efivars prefix every variable with a 4-byte attribute mask.
+    let data = sysroot::read(SECURE_BOOT_VAR).ok()?;
+    data.get(4).map(|value| *value == 1)
+}
+
+fn installed_kernels(modules_dir: &Path) -> Vec<String> {
+    match fs::read_dir(modules_dir) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.path().join("modules.dep").exists() || entry.path().join("kernel").exists())
+            .filter_map(|entry| entry.file_name().to_str().map(|s| s.to_string()))
+            .collect(),
+        Err(_) => Vec::new(),
+    }
+}
+
+/// The non-numeric parts of a kernel release, e.g. `arch` for `6.9.3-arch1-1`, `lts` for
+/// `6.6.31-1-lts` and `amd` for `6.1.0-21-amd64`. Upgrades keep the flavor; a second kernel
+/// package installed alongside (linux-lts, linux-zen, a cloud image) has a different one.
+fn kernel_flavor(release: &str) -> Vec<String> {
+    release
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .map(|part| part.chars().filter(|c| !c.is_ascii_digit()).collect::<String>())
+        .filter(|part| !part.is_empty())
+        .collect()
+}
+
+fn newest_of_flavor<'a>(running: &str, installed: &'a [String]) -> Option<&'a String> {
+    let flavor = kernel_flavor(running);
+    installed
+        .iter()
+        .filter(|release| kernel_flavor(release) == flavor)
+        .max_by(|a, b| compare_kernel_versions(a, b))
+}
+
+/// A newer kernel of the same flavor than the running one is installed. Unknown, and so `false`,
+/// when the running kernel is not under `/lib/modules` at all: containers share the host's kernel
+/// and custom kernels may keep their modules elsewhere.
+fn kernel_outdated(running: &str, installed: &[String]) -> bool {
+    if !installed.iter().any(|release| release == running) {
+        return false;
+    }
+    newest_of_flavor(running, installed)
+        .map(|newest| compare_kernel_versions(newest, running) == Ordering::Greater)
+        .unwrap_or(false)
+}
+
+pub fn compare_kernel_versions(a: &str, b: &str) -> Ordering {
+    let tokens = |s: &str| -> Vec<u64> {
+        s.split(|c: char| !c.is_ascii_digit())
+            .filter(|part| !part.is_empty())
+            .filter_map(|part| part.parse().ok())
+            .collect()
+    };
+
+    tokens(a).cmp(&tokens(b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_lockdown() {
+        assert_eq!(parse_lockdown("none [integrity] confidentiality\n"), Some("integrity".to_string()));
+        assert_eq!(parse_lockdown("none integrity"), None);
+    }
+
+    #[test]
+    fn test_compare_kernel_versions() {
+        assert_eq!(compare_kernel_versions("6.10.0-arch1-1", "6.9.12-arch1-1"), Ordering::Greater);
+        assert_eq!(compare_kernel_versions("6.1.0-18-amd64", "6.1.0-21-amd64"), Ordering::Less);
+        assert_eq!(compare_kernel_versions("6.8.9", "6.8.9"), Ordering::Equal);
+    }
+
+    #[test]
+    fn test_kernel_outdated_per_flavor() {
+        let installed: Vec<String> =
+            ["6.9.1-arch1-1", "6.9.3-arch1-1", "6.6.31-1-lts"].iter().map(|s| s.to_string()).collect();
+        assert!(!kernel_outdated("6.6.31-1-lts", &installed));
+        assert!(!kernel_outdated("6.9.3-arch1-1", &installed));
+        assert!(kernel_outdated("6.9.1-arch1-1", &installed));
+        assert_eq!(newest_of_flavor("6.6.31-1-lts", &installed).map(String::as_str), Some("6.6.31-1-lts"));
+
+        let debian: Vec<String> = ["6.1.0-18-amd64", "6.1.0-21-amd64", "6.1.0-21-cloud-amd64"]
+            .iter().map(|s| s.to_string()).collect();
+        assert!(kernel_outdated("6.1.0-18-amd64", &debian));
+        assert!(!kernel_outdated("6.1.0-21-amd64", &debian));
+    }
+
+    #[test]
+    fn test_kernel_outdated_unknown_without_running_modules() {
+        // A container on a 6.8 host whose image ships the distribution's kernel packages.
+        let installed: Vec<String> = ["6.1.0-21-amd64"].iter().map(|s| s.to_string()).collect();
+        assert!(!kernel_outdated("6.1.0-18-amd64", &installed));
+        assert!(!kernel_outdated("6.8.0-1-cloud-amd64", &installed));
+        assert!(!kernel_outdated("6.1.0-18-amd64", &[]));
+    }
+}
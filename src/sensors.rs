@@ -0,0 +1,233 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::sysroot;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SensorsInfo {
+    pub temperatures: Vec<TemperatureReading>,
+    pub fans: Vec<FanReading>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TemperatureReading {
+    pub kind: SensorKind,
+    pub chip: String,
+    pub label: String,
+    pub celsius: f64,
+    pub high: Option<f64>,
+    pub critical: Option<f64>,
+    pub source: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FanReading {
+    pub chip: String,
+    pub label: String,
+    pub rpm: u64,
+    pub source: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SensorKind {
+    Cpu,
+    Gpu,
+    Nvme,
+    Board,
+    Other,
+}
+
+impl SensorKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            SensorKind::Cpu => "CPU",
+            SensorKind::Gpu => "GPU",
+            SensorKind::Nvme => "NVMe",
+            SensorKind::Board => "Board",
+            SensorKind::Other => "Other",
+        }
+    }
+
+    fn from_chip(chip: &str) -> Self {
+        let chip = chip.to_lowercase();
+        if chip == "coretemp" || chip == "k10temp" || chip == "zenpower" || chip.starts_with("cpu")
+            || chip == "x86_pkg_temp" || chip.starts_with("soc")
+        {
+            SensorKind::Cpu
+        } else if chip == "amdgpu" || chip == "nouveau" || chip == "radeon" || chip == "i915"
+            || chip == "xe" || chip.starts_with("gpu")
+        {
+            SensorKind::Gpu
+        } else if chip.starts_with("nvme") {
+            SensorKind::Nvme
+        } else if chip.starts_with("nct") || chip.starts_with("it8") || chip.starts_with("asus")
+            || chip.starts_with("acpitz") || chip.starts_with("pch") || chip.starts_with("dell")
+            || chip.starts_with("thinkpad")
+        {
+            SensorKind::Board
+        } else {
+            SensorKind::Other
+        }
+    }
+}
+
+impl SensorsInfo {
+    pub fn is_empty(&self) -> bool {
+        self.temperatures.is_empty() && self.fans.is_empty()
+    }
+
+    pub fn hottest(&self, kind: SensorKind) -> Option<&TemperatureReading> {
+        self.temperatures
+            .iter()
+            .filter(|reading| reading.kind == kind)
+            .max_by(|a, b| a.celsius.total_cmp(&b.celsius))
+    }
+}
+
+pub fn get_sensors() -> SensorsInfo {
+    let mut info = SensorsInfo {
+        temperatures: Vec::new(),
+        fans: Vec::new(),
+    };
+
+    read_hwmon(Path::new("/sys/class/hwmon"), &mut info);
+
+    // Thermal zones mostly mirror hwmon on desktops, but are the only source on many ARM boards.
+    if info.temperatures.is_empty() {
+        read_thermal_zones(Path::new("/sys/class/thermal"), &mut info);
+    }
+
+    info.temperatures.sort_by_key(|reading| reading.kind);
+    info
+}
+
+fn read_trimmed(path: &Path) -> Option<String> {
+    sysroot::read_to_string(path).ok().map(|s| s.trim().to_string())
+}
+
+/// Paths on the described system of the entries in `dir`, sorted.
+fn list(dir: &Path) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = match sysroot::read_dir(dir) {
+        Ok(entries) => entries.filter_map(|e| e.ok()).map(|e| dir.join(e.file_name())).collect(),
+        Err(_) => Vec::new(),
+    };
+    paths.sort();
+    paths
+}
+
+fn read_millidegrees(path: &Path) -> Option<f64> {
+    read_trimmed(path)?.parse::<i64>().ok().map(|m| m as f64 / 1000.0)
+}
+
+fn read_hwmon(root: &Path, info: &mut SensorsInfo) {
+    for chip_dir in list(root) {
+        let chip = read_trimmed(&chip_dir.join("name")).unwrap_or_else(|| "unknown".to_string());
+        let kind = SensorKind::from_chip(&chip);
+
+        let files: Vec<String> = list(&chip_dir)
+            .iter()
+            .filter_map(|path| path.file_name().and_then(|name| name.to_str()).map(|name| name.to_string()))
+            .collect();
+
+        for file in &files {
+            if let Some(index) = file.strip_prefix("temp").and_then(|f| f.strip_suffix("_input")) {
+                let input = chip_dir.join(file);
+                if let Some(celsius) = read_millidegrees(&input) {
+                    let label = read_trimmed(&chip_dir.join(format!("temp{}_label", index)))
+                        .unwrap_or_else(|| format!("temp{}", index));
+                    info.temperatures.push(TemperatureReading {
+                        kind,
+                        chip: chip.clone(),
+                        label,
+                        celsius,
+                        high: read_millidegrees(&chip_dir.join(format!("temp{}_max", index))),
+                        critical: read_millidegrees(&chip_dir.join(format!("temp{}_crit", index))),
+                        source: input.to_string_lossy().to_string(),
+                    });
+                }
+            } else if let Some(index) = file.strip_prefix("fan").and_then(|f| f.strip_suffix("_input")) {
+                let input = chip_dir.join(file);
+                if let Some(rpm) = read_trimmed(&input).and_then(|s| s.parse().ok()) {
+                    let label = read_trimmed(&chip_dir.join(format!("fan{}_label", index)))
+                        .unwrap_or_else(|| format!("fan{}", index));
+                    info.fans.push(FanReading {
+                        chip: chip.clone(),
+                        label,
+                        rpm,
+                        source: input.to_string_lossy().to_string(),
+                    });
+                }
+            }
+        }
+    }
+}
+
+fn read_thermal_zones(root: &Path, info: &mut SensorsInfo) {
+    let zones: Vec<PathBuf> = list(root)
+        .into_iter()
+        .filter(|path| {
+            path.file_name()
+                .and_then(|n| n.to_str())
+                .map(|n| n.starts_with("thermal_zone"))
+                .unwrap_or(false)
+        })
+        .collect();
+
+    for zone in zones {
+        let input = zone.join("temp");
+        if let Some(celsius) = read_millidegrees(&input) {
+            let zone_type = read_trimmed(&zone.join("type")).unwrap_or_else(|| "thermal".to_string());
+            let kind = match SensorKind::from_chip(&zone_type) {
+                SensorKind::Other => SensorKind::Board,
+                kind => kind,
+            };
+            info.temperatures.push(TemperatureReading {
+                kind,
+                chip: "thermal_zone".to_string(),
+                label: zone_type,
+                celsius,
+                high: None,
+                critical: None,
+                source: input.to_string_lossy().to_string(),
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_chip_classification() {
+        assert_eq!(SensorKind::from_chip("k10temp"), SensorKind::Cpu);
+        assert_eq!(SensorKind::from_chip("amdgpu"), SensorKind::Gpu);
+        assert_eq!(SensorKind::from_chip("nvme"), SensorKind::Nvme);
+        assert_eq!(SensorKind::from_chip("nct6798"), SensorKind::Board);
+        assert_eq!(SensorKind::from_chip("iwlwifi_1"), SensorKind::Other);
+    }
+
+    #[test]
+    fn test_read_hwmon_tree() {
+        let dir = tempfile::tempdir().unwrap();
+        let chip = dir.path().join("sys/class/hwmon/hwmon0");
+        fs::create_dir_all(&chip).unwrap();
+        fs::write(chip.join("name"), "coretemp\n").unwrap();
+        fs::write(chip.join("temp1_input"), "54000\n").unwrap();
+        fs::write(chip.join("temp1_label"), "Package id 0\n").unwrap();
+        fs::write(chip.join("temp1_crit"), "100000\n").unwrap();
+        fs::write(chip.join("fan1_input"), "1200\n").unwrap();
+
+        let _scope = sysroot::enter(Some(dir.path().to_path_buf()));
+        let info = get_sensors();
+
+        assert_eq!(info.temperatures.len(), 1);
+        assert_eq!(info.temperatures[0].label, "Package id 0");
+        assert_eq!(info.temperatures[0].celsius, 54.0);
+        assert_eq!(info.temperatures[0].critical, Some(100.0));
+        assert_eq!(info.temperatures[0].source, "/sys/class/hwmon/hwmon0/temp1_input");
+        assert_eq!(info.fans[0].rpm, 1200);
+    }
+}
@@ -0,0 +1,184 @@
+//! Row counts read straight from SQLite database files, for package databases such as rpm's
+//! `rpmdb.sqlite`, without linking SQLite or running the package manager.
+//!
+//! Only table b-trees are walked; a row count is the number of cells on the table's leaf pages.
+//! See <https://www.sqlite.org/fileformat2.html>.
+
+const MAGIC: &[u8] = b"SQLite format 3\0";
+const HEADER_SIZE: usize = 100;
+const LEAF_TABLE: u8 = 0x0d;
+const INTERIOR_TABLE: u8 = 0x05;
+/// Deeper than any real table b-tree; stops a corrupt file whose pages point in a loop.
+const MAX_DEPTH: usize = 32;
+
+/// The number of rows in `table`, or `None` if `data` is not a SQLite database with that table.
+pub fn count_rows(data: &[u8], table: &str) -> Option<u64> {
+    if data.len() < HEADER_SIZE || !data.starts_with(MAGIC) {
+        return None;
+    }
+    let page_size = match u16::from_be_bytes([data[16], data[17]]) {
+        1 => 65536,
+        size => size as usize,
+    };
+    let db = Database { data, page_size, usable: page_size.checked_sub(data[20] as usize)? };
+
+    // The schema table, rooted at page 1, holds `(type, name, tbl_name, rootpage, sql)` rows.
+    let mut root = None;
+    db.walk(1, 0, &mut |cell| {
+        if root.is_none() {
+            root = db.schema_root(cell, table);
+        }
+    })?;
+
+    let mut rows = 0;
+    db.walk(root?, 0, &mut |_| rows += 1)?;
+    Some(rows)
+}
+
+struct Database<'a> {
+    data: &'a [u8],
+    page_size: usize,
+    usable: usize,
+}
+
+impl Database<'_> {
+    /// Calls `leaf` with the offset of every cell on the leaf pages below `page`.
+    fn walk(&self, page: u32, depth: usize, leaf: &mut dyn FnMut(usize)) -> Option<()> {
+        if depth > MAX_DEPTH || page == 0 {
+            return None;
+        }
+        let start = (page as usize - 1) * self.page_size;
+        // Page 1 starts with the database header.
+        let header = if page == 1 { start + HEADER_SIZE } else { start };
+        let kind = *self.data.get(header)?;
+        let cells = self.u16(header + 3)? as usize;
+        let pointers = header + if kind == INTERIOR_TABLE { 12 } else { 8 };
+        let cell = |i: usize| self.u16(pointers + 2 * i).map(|offset| start + offset as usize);
+
+        match kind {
+            LEAF_TABLE => (0..cells).try_for_each(|i| cell(i).map(&mut *leaf)),
+            INTERIOR_TABLE => {
+                for i in 0..cells {
+                    self.walk(self.u32(cell(i)?)?, depth + 1, leaf)?;
+                }
+                self.walk(self.u32(header + 8)?, depth + 1, leaf)
+            }
+            _ => None,
+        }
+    }
+
+    /// The root page of `table` if the schema row at `cell` describes it.
+    fn schema_root(&self, cell: usize, table: &str) -> Option<u32> {
+        let (payload_size, used) = self.varint(cell)?;
+        let (_rowid, rowid_len) = self.varint(cell + used)?;
+        let payload = cell + used + rowid_len;
+        // The leading columns always fit on the page unless the payload spills to overflow pages,
+        // in which case at least the minimum local part does.
+        let local = (payload_size as usize).min(self.usable.saturating_sub(35));
+        let record = self.data.get(payload..payload + local)?;
+
+        let (header_size, mut offset) = varint(record)?;
+        let mut types = Vec::new();
+        while offset < header_size as usize && types.len() < 4 {
+            let (serial, len) = varint(record.get(offset..)?)?;
+            types.push(serial);
+            offset += len;
+        }
+        let mut values = Vec::new();
+        let mut body = header_size as usize;
+        for serial in types {
+            let len = serial_len(serial);
+            values.push((serial, record.get(body..body + len)?));
+            body += len;
+        }
+
+        match values[..] {
+            [(_, kind), (_, name), _, (serial, root)] if kind == b"table" && name == table.as_bytes() => {
+                int(serial, root).map(|root| root as u32)
+            }
+            _ => None,
+        }
+    }
+
+    fn u16(&self, at: usize) -> Option<u16> {
+        self.data.get(at..at + 2).map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u32(&self, at: usize) -> Option<u32> {
+        self.data.get(at..at + 4).map(|bytes| u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn varint(&self, at: usize) -> Option<(u64, usize)> {
+        varint(self.data.get(at..)?)
+    }
+}
+
+/// A SQLite varint: up to nine bytes, big-endian, seven bits per byte and all eight in the last.
+fn varint(bytes: &[u8]) -> Option<(u64, usize)> {
+    let mut value = 0u64;
+    for (i, byte) in bytes.iter().take(9).enumerate() {
+        if i == 8 {
+            return Some(((value << 8) | *byte as u64, 9));
+        }
+        value = (value << 7) | (byte & 0x7f) as u64;
+        if byte & 0x80 == 0 {
+            return Some((value, i + 1));
+        }
+    }
+    None
+}
+
+/// Size in the record body of a value with this serial type.
+fn serial_len(serial: u64) -> usize {
+    match serial {
+        0 | 8 | 9 => 0,
+        1..=4 => serial as usize,
+        5 => 6,
+        6 | 7 => 8,
+        serial if serial >= 12 => ((serial - 12) / 2) as usize,
+        _ => 0,
+    }
+}
+
+fn int(serial: u64, bytes: &[u8]) -> Option<i64> {
+    match serial {
+        8 => Some(0),
+        9 => Some(1),
+        1..=6 => {
+            let mut value = if bytes.first()? & 0x80 != 0 { -1i64 } else { 0 };
+            for byte in bytes {
+                value = (value << 8) | *byte as i64;
+            }
+            Some(value)
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_varint() {
+        assert_eq!(varint(&[0x05]), Some((5, 1)));
+        assert_eq!(varint(&[0x81, 0x00]), Some((128, 2)));
+        assert_eq!(varint(&[0x81]), None);
+    }
+
+    #[test]
+    fn test_count_rows() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/fedora/var/lib/rpm/rpmdb.sqlite");
+        let data = std::fs::read(path).unwrap();
+        assert_eq!(count_rows(&data, "Packages"), Some(4));
+        assert_eq!(count_rows(&data, "Name"), Some(4));
+        assert_eq!(count_rows(&data, "Name_key_idx"), None);
+        assert_eq!(count_rows(&data, "Basenames"), None);
+    }
+
+    #[test]
+    fn test_rejects_other_files() {
+        assert_eq!(count_rows(b"not a database", "Packages"), None);
+        assert_eq!(count_rows(&[0; 200], "Packages"), None);
+    }
+}
@@ -0,0 +1,282 @@
+//! Filesystem and command access for detectors, so a captured `/proc`, `/sys` and `/etc` tree
+//! can stand in for the live system (`--sysroot`, fixture tests).
+//!
+//! Under a sysroot no command is ever spawned: `run("uname", &["-r"])` returns the contents of
+//! `<sysroot>/.rfetch/commands/uname -r` instead, and a missing stub behaves like a program that
+//! is not installed. Filesystem usage comes from `<sysroot>/.rfetch/statvfs`, one
+//! `<mount point> <total> <free> <available>` line (in bytes) per filesystem.
+//!
+//! Every read, command and environment variable goes to the debug log ([`crate::trace`]).
+
+use std::cell::{Cell, RefCell};
+use std::env;
+use std::ffi::OsStr;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus, Output};
+use std::time::{Duration, Instant};
+
+use crate::utils::output_with_timeout;
+use crate::trace::{self, trace};
+
+pub const COMMANDS_DIR: &str = ".rfetch/commands";
+pub const STATVFS_FILE: &str = ".rfetch/statvfs";
+
+/// How long a command run through [`run`] may take, unless the module's deadline comes first.
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Sizes in bytes of the filesystem mounted at a path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FsUsage {
+    pub total: u64,
+    pub free: u64,
+    /// Free space usable by unprivileged users; excludes the root reserve.
+    pub available: u64,
+}
+
+thread_local! {
+    static ROOT: RefCell<Option<PathBuf>> = const { RefCell::new(None) };
+    static DEADLINE: Cell<Option<Instant>> = const { Cell::new(None) };
+}
+
+/// Restores the thread's previous sysroot when dropped.
+pub struct Scope {
+    previous: Option<PathBuf>,
+}
+
+/// Points detectors running on the current thread at `root`, or at the live system for `None`.
+pub fn enter(root: Option<PathBuf>) -> Scope {
+    Scope { previous: ROOT.with(|current| current.replace(root)) }
+}
+
+impl Drop for Scope {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        ROOT.with(|current| *current.borrow_mut() = previous);
+    }
+}
+
+/// Restores the thread's previous deadline when dropped.
+pub struct Deadline {
+    previous: Option<Instant>,
+}
+
+/// Kills commands spawned on the current thread once `at` passes, so a module that times out does
+/// not leave its helpers running after rfetch exits.
+pub fn deadline(at: Instant) -> Deadline {
+    Deadline { previous: DEADLINE.with(|deadline| deadline.replace(Some(at))) }
+}
+
+impl Drop for Deadline {
+    fn drop(&mut self) {
+        DEADLINE.with(|deadline| deadline.set(self.previous));
+    }
+}
+
+pub fn current_deadline() -> Option<Instant> {
+    DEADLINE.with(|deadline| deadline.get())
+}
+
+/// `timeout`, shortened to what is left until the current thread's deadline.
+pub fn remaining(timeout: Duration) -> Duration {
+    match current_deadline() {
+        Some(deadline) => timeout.min(deadline.saturating_duration_since(Instant::now())),
+        None => timeout,
+    }
+}
+
+pub fn current() -> Option<PathBuf> {
+    ROOT.with(|root| root.borrow().clone())
+}
+
+pub fn is_live() -> bool {
+    ROOT.with(|root| root.borrow().is_none())
+}
+
+/// Maps an absolute system path to its location under the current sysroot.
+pub fn path<P: AsRef<Path>>(path: P) -> PathBuf {
+    let path = path.as_ref();
+    match current() {
+        Some(root) => root.join(path.strip_prefix("/").unwrap_or(path)),
+        None => path.to_path_buf(),
+    }
+}
+
+pub fn read_to_string<P: AsRef<Path>>(file: P) -> io::Result<String> {
+    let content = fs::read_to_string(path(&file));
+    log_access("read", file.as_ref(), &content);
+    content
+}
+
+pub fn read<P: AsRef<Path>>(file: P) -> io::Result<Vec<u8>> {
+    let content = fs::read(path(&file));
+    log_access("read", file.as_ref(), &content);
+    content
+}
+
+/// Entries carry host paths (already under the sysroot), so read them with `std::fs`.
+pub fn read_dir<P: AsRef<Path>>(dir: P) -> io::Result<fs::ReadDir> {
+    let entries = fs::read_dir(path(&dir));
+    log_access("list", dir.as_ref(), &entries);
+    entries
+}
+
+/// Returns the link target as stored, which is a path on the system being described.
+pub fn read_link<P: AsRef<Path>>(link: P) -> io::Result<PathBuf> {
+    let target = fs::read_link(path(&link));
+    match &target {
+        Ok(target) => trace!("readlink {} -> {}", link.as_ref().display(), target.display()),
+        Err(e) => trace!("readlink {}: {}", link.as_ref().display(), e),
+    }
+    target
+}
+
+pub fn exists<P: AsRef<Path>>(file: P) -> bool {
+    let exists = path(&file).exists();
+    trace!("exists {}: {}", file.as_ref().display(), if exists { "yes" } else { "no" });
+    exists
+}
+
+/// An environment variable of the rfetch process. A sysroot does not capture the environment,
+/// so this is always the live value; it goes through here to be logged.
+pub fn var(name: &str) -> Result<String, env::VarError> {
+    let value = env::var(name);
+    match &value {
+        Ok(value) => trace!("env {}={:?}", name, value),
+        Err(_) => trace!("env {} is not set", name),
+    }
+    value
+}
+
+fn log_access<T>(action: &str, file: &Path, result: &io::Result<T>) {
+    if trace::enabled() {
+        match result {
+            Ok(_) => trace!("{} {}", action, file.display()),
+            Err(e) => trace!("{} {}: {}", action, file.display(), e),
+        }
+    }
+}
+
+pub fn run<S: AsRef<OsStr>>(program: S, args: &[&str]) -> io::Result<Output> {
+    run_with_timeout(program, args, COMMAND_TIMEOUT)
+}
+
+/// Like [`run`], with a caller-chosen limit instead of the default five seconds.
+pub fn run_with_timeout<S: AsRef<OsStr>>(program: S, args: &[&str], timeout: Duration) -> io::Result<Output> {
+    match current() {
+        None => {
+            output_with_timeout(Command::new(program.as_ref()).args(args), timeout).ok_or_else(|| {
+                io::Error::other(format!("`{}` did not run to completion", command_line(program.as_ref(), args)))
+            })
+        }
+        Some(root) => {
+            let stub = stub_name(program.as_ref(), args);
+            let stdout = fs::read(root.join(COMMANDS_DIR).join(&stub));
+            match &stdout {
+                Ok(_) => trace!("run `{}`: stub {}/{}", command_line(program.as_ref(), args), COMMANDS_DIR, stub),
+                Err(_) => trace!("run `{}`: no stub, not installed", command_line(program.as_ref(), args)),
+            }
+            Ok(Output { status: success(), stdout: stdout?, stderr: Vec::new() })
+        }
+    }
+}
+
+#[cfg(unix)]
+pub fn statvfs<P: AsRef<Path>>(mount_point: P) -> io::Result<FsUsage> {
+    let mount_point = mount_point.as_ref();
+    let usage = match current() {
+        None => {
+            let stats = nix::sys::statvfs::statvfs(mount_point)?;
+            let block = stats.fragment_size() as u64;
+            Ok(FsUsage {
+                total: stats.blocks() as u64 * block,
+                free: stats.blocks_free() as u64 * block,
+                available: stats.blocks_available() as u64 * block,
+            })
+        }
+        Some(root) => fs::read_to_string(root.join(STATVFS_FILE))?
+            .lines()
+            .find_map(|line| {
+                let fields: Vec<&str> = line.split_whitespace().collect();
+                match fields[..] {
+                    [mount, total, free, available] if Path::new(mount) == mount_point => Some(FsUsage {
+                        total: total.parse().ok()?,
+                        free: free.parse().ok()?,
+                        available: available.parse().ok()?,
+                    }),
+                    _ => None,
+                }
+            })
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no statvfs entry in sysroot")),
+    };
+    match &usage {
+        Ok(usage) => trace!(
+            "statvfs {}: {} total, {} free, {} available bytes",
+            mount_point.display(),
+            usage.total,
+            usage.free,
+            usage.available
+        ),
+        Err(e) => trace!("statvfs {}: {}", mount_point.display(), e),
+    }
+    usage
+}
+
+/// Stubs are keyed by the bare program name, so `/usr/bin/bash --version` and `bash --version`
+/// share one file.
+fn command_line(program: &OsStr, args: &[&str]) -> String {
+    std::iter::once(program.to_string_lossy().as_ref()).chain(args.iter().copied()).collect::<Vec<_>>().join(" ")
+}
+
+fn stub_name(program: &OsStr, args: &[&str]) -> String {
+    let program = Path::new(program).file_name().unwrap_or(program).to_string_lossy();
+    std::iter::once(program.as_ref())
+        .chain(args.iter().copied())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+        .replace('/', "_")
+}
+
+#[cfg(unix)]
+fn success() -> ExitStatus {
+    use std::os::unix::process::ExitStatusExt;
+    ExitStatus::from_raw(0)
+}
+
+#[cfg(windows)]
+fn success() -> ExitStatus {
+    use std::os::windows::process::ExitStatusExt;
+    ExitStatus::from_raw(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_paths_and_stubs_follow_scope() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("etc")).unwrap();
+        fs::write(dir.path().join("etc/hostname"), "fixture\n").unwrap();
+        fs::create_dir_all(dir.path().join(COMMANDS_DIR)).unwrap();
+        fs::write(dir.path().join(COMMANDS_DIR).join("uname -r"), "6.9.1-arch1-1\n").unwrap();
+        fs::write(dir.path().join(STATVFS_FILE), "/ 1000 400 300\n").unwrap();
+
+        {
+            let _scope = enter(Some(dir.path().to_path_buf()));
+            assert_eq!(read_to_string("/etc/hostname").unwrap(), "fixture\n");
+            assert_eq!(run("uname", &["-r"]).unwrap().stdout, b"6.9.1-arch1-1\n");
+            assert!(run("lspci", &[]).is_err());
+            #[cfg(unix)]
+            {
+                assert_eq!(statvfs("/").unwrap(), FsUsage { total: 1000, free: 400, available: 300 });
+                assert!(statvfs("/home").is_err());
+            }
+        }
+
+        assert!(is_live());
+        assert_eq!(path("/etc/hostname"), Path::new("/etc/hostname"));
+    }
+}
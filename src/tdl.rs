@@ -2,7 +2,8 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
 use std::path::Path;
-use crate::themes::{Theme, AdvancedColor, ThemeColors, ThemeDisplay, ThemeEffects, ColorEffect, Animation, AnimationType};
+use crate::error::RFetchError;
+use crate::themes::{Theme, AdvancedColor, ThemeColors, ThemeDisplay, ThemeEffects, ThemeSection, ColorEffect, Animation, AnimationType};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TdlTheme {
@@ -129,6 +130,44 @@ pub struct TdlAscii {
     pub frame_delay: Option<f32>,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TdlFormat {
+    Json,
+    Yaml,
+    Toml,
+}
+
+impl TdlFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "json" => Some(TdlFormat::Json),
+            "yaml" | "yml" => Some(TdlFormat::Yaml),
+            "toml" => Some(TdlFormat::Toml),
+            _ => None,
+        }
+    }
+
+    /// Guesses the format of a theme file from its content.
+    pub fn detect(content: &str) -> Self {
+        if content.trim_start().starts_with('{') {
+            TdlFormat::Json
+        } else if content.contains("name =") || content.contains("[meta]") {
+            TdlFormat::Toml
+        } else {
+            TdlFormat::Yaml
+        }
+    }
+
+    pub fn serialize(self, theme: &TdlTheme) -> Result<String, RFetchError> {
+        let content = match self {
+            TdlFormat::Json => serde_json::to_string_pretty(theme)?,
+            TdlFormat::Yaml => serde_yaml::to_string(theme).map_err(|e| RFetchError::theme(e.to_string()))?,
+            TdlFormat::Toml => toml::to_string_pretty(theme).map_err(|e| RFetchError::theme(e.to_string()))?,
+        };
+        Ok(content)
+    }
+}
+
 pub struct TdlParser;
 
 impl TdlParser {
@@ -138,15 +177,26 @@ impl TdlParser {
     }
 
     pub fn parse_string(content: &str) -> Result<TdlTheme, Box<dyn std::error::Error>> {
-        if content.trim_start().starts_with('{') {
-            Ok(serde_json::from_str(content)?)
-        } else if content.contains("name =") || content.contains("[meta]") {
-            Ok(toml::from_str(content)?)
-        } else {
-            Ok(serde_yaml::from_str(content)?)
+        match TdlFormat::detect(content) {
+            TdlFormat::Json => Ok(serde_json::from_str(content)?),
+            TdlFormat::Toml => Ok(toml::from_str(content)?),
+            TdlFormat::Yaml => Ok(serde_yaml::from_str(content)?),
         }
     }
 
+    /// Parses a TDL file into a ready-to-use [`Theme`].
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Theme, RFetchError> {
+        Self::parse_file(path)
+            .map(Self::to_theme)
+            .map_err(|e| RFetchError::theme(e.to_string()))
+    }
+
+    /// Re-serializes a theme in another format, e.g. a YAML theme as TOML.
+    pub fn convert(content: &str, format: TdlFormat) -> Result<String, RFetchError> {
+        let theme = Self::parse_string(content).map_err(|e| RFetchError::theme(e.to_string()))?;
+        format.serialize(&theme)
+    }
+
     pub fn to_theme(tdl: TdlTheme) -> Theme {
         Theme {
             name: tdl.meta.name,
@@ -157,6 +207,7 @@ impl TdlParser {
             display: Self::convert_display(tdl.display),
             ascii: tdl.ascii.map(|ascii| ascii.logo.join("\n")),
             effects: Self::convert_effects(tdl.effects),
+            sections: Self::convert_sections(tdl.layout.sections),
         }
     }
 
@@ -170,6 +221,8 @@ impl TdlParser {
             logo: Self::convert_color(colors.logo),
             accent: Some(Self::convert_color(colors.accent)),
             background: colors.background.map(Self::convert_color),
+            warning: colors.warning.map(Self::convert_color),
+            error: colors.error.map(Self::convert_color),
         }
     }
 
@@ -218,6 +271,19 @@ impl TdlParser {
         }
     }
 
+    fn convert_sections(sections: Option<Vec<TdlSection>>) -> Vec<ThemeSection> {
+        sections
+            .unwrap_or_default()
+            .into_iter()
+            .map(|section| ThemeSection {
+                name: section.name,
+                title: section.title,
+                items: section.items,
+                visible: section.visible.unwrap_or(true),
+            })
+            .collect()
+    }
+
     fn convert_effects(effects: Option<TdlEffects>) -> ThemeEffects {
         if let Some(effects) = effects {
             ThemeEffects {
@@ -282,27 +348,13 @@ pub struct TdlGenerator;
 
 impl TdlGenerator {
     pub fn generate_template(format: &str) -> String {
-        match format.to_lowercase().as_str() {
-            "json" => Self::generate_json_template(),
-            "toml" => Self::generate_toml_template(),
-            "yaml" | "yml" => Self::generate_yaml_template(),
-            _ => Self::generate_yaml_template(),
-        }
+        TdlFormat::from_name(format)
+            .unwrap_or(TdlFormat::Yaml)
+            .serialize(&Self::create_template())
+            .unwrap_or_default()
     }
 
-    fn generate_json_template() -> String {
-        serde_json::to_string_pretty(&Self::create_template()).unwrap_or_default()
-    }
-
-    fn generate_toml_template() -> String {
-        toml::to_string_pretty(&Self::create_template()).unwrap_or_default()
-    }
-
-    fn generate_yaml_template() -> String {
-        serde_yaml::to_string(&Self::create_template()).unwrap_or_default()
-    }
-
-    fn create_template() -> TdlTheme {
+    pub fn create_template() -> TdlTheme {
         TdlTheme {
             meta: TdlMeta {
                 name: "my_theme".to_string(),
@@ -537,6 +589,14 @@ mod tests {
         assert!(!template.colors.title.base.is_empty());
     }
 
+    #[test]
+    fn test_convert_round_trip() {
+        let yaml = TdlGenerator::generate_template("yaml");
+        let toml = TdlParser::convert(&yaml, TdlFormat::Toml).unwrap();
+        assert_eq!(TdlFormat::detect(&toml), TdlFormat::Toml);
+        assert_eq!(TdlParser::parse_string(&toml).unwrap().meta.name, "my_theme");
+    }
+
     #[test]
     fn test_validation() {
         let template = TdlGenerator::create_template();
@@ -1,7 +1,7 @@
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(from = "ColorSpec")]
 pub struct AdvancedColor {
     pub base: String,
     pub rgb: Option<(u8, u8, u8)>,
@@ -10,6 +10,35 @@ pub struct AdvancedColor {
     pub gradient: Option<Gradient>,
 }
 
+/// A color as written in a config file: a plain name (`title = "cyan"`) or a full table.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ColorSpec {
+    Name(String),
+    Full {
+        base: String,
+        #[serde(default)]
+        rgb: Option<(u8, u8, u8)>,
+        #[serde(default)]
+        effects: Vec<ColorEffect>,
+        #[serde(default)]
+        animation: Option<Animation>,
+        #[serde(default)]
+        gradient: Option<Gradient>,
+    },
+}
+
+impl From<ColorSpec> for AdvancedColor {
+    fn from(spec: ColorSpec) -> Self {
+        match spec {
+            ColorSpec::Name(name) => AdvancedColor::from(name),
+            ColorSpec::Full { base, rgb, effects, animation, gradient } => {
+                AdvancedColor { base, rgb, effects, animation, gradient }
+            }
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ColorEffect {
     Bold,
@@ -64,6 +93,16 @@ pub struct Theme {
     pub display: ThemeDisplay,
     pub ascii: Option<String>,
     pub effects: ThemeEffects,
+    #[serde(default)]
+    pub sections: Vec<ThemeSection>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThemeSection {
+    pub name: String,
+    pub title: Option<String>,
+    pub items: Vec<String>,
+    pub visible: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -76,6 +115,10 @@ pub struct ThemeColors {
     pub logo: AdvancedColor,
     pub accent: Option<AdvancedColor>,
     pub background: Option<AdvancedColor>,
+    #[serde(default)]
+    pub warning: Option<AdvancedColor>,
+    #[serde(default)]
+    pub error: Option<AdvancedColor>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -219,6 +262,8 @@ fn create_default_theme() -> Theme {
             logo: AdvancedColor::new("cyan"),
             accent: Some(AdvancedColor::new("magenta")),
             background: None,
+            warning: None,
+            error: None,
         },
         display: ThemeDisplay {
             logo_type: "auto".to_string(),
@@ -235,6 +280,7 @@ fn create_default_theme() -> Theme {
             shadows: false,
             transparency: None,
         },
+        sections: Vec::new(),
     }
 }
 
@@ -268,6 +314,8 @@ fn create_neon_theme() -> Theme {
                 .pulse(1.5)),
             background: Some(AdvancedColor::new("black")
                 .with_rgb(5, 5, 15)),
+            warning: Some(AdvancedColor::new("bright_yellow").bold()),
+            error: Some(AdvancedColor::new("bright_red").bold()),
         },
         display: ThemeDisplay {
             logo_type: "ascii".to_string(),
@@ -291,6 +339,7 @@ fn create_neon_theme() -> Theme {
             shadows: true,
             transparency: Some(0.95),
         },
+        sections: Vec::new(),
     }
 }
 
@@ -309,6 +358,8 @@ fn create_minimal_theme() -> Theme {
             logo: AdvancedColor::new("white"),
             accent: None,
             background: None,
+            warning: None,
+            error: None,
         },
         display: ThemeDisplay {
             logo_type: "small".to_string(),
@@ -325,6 +376,7 @@ fn create_minimal_theme() -> Theme {
             shadows: false,
             transparency: None,
         },
+        sections: Vec::new(),
     }
 }
 
@@ -343,6 +395,8 @@ fn create_retro_theme() -> Theme {
             logo: AdvancedColor::new("bright_green").bold(),
             accent: Some(AdvancedColor::new("yellow")),
             background: Some(AdvancedColor::new("black")),
+            warning: None,
+            error: None,
         },
         display: ThemeDisplay {
             logo_type: "ascii".to_string(),
@@ -367,5 +421,6 @@ fn create_retro_theme() -> Theme {
             shadows: false,
             transparency: None,
         },
+        sections: Vec::new(),
     }
 }
\ No newline at end of file
@@ -0,0 +1,248 @@
+//! What each module cost during a gather: wall time and the external commands it spawned.
+//!
+//! Every gather thread installs a recorder with [`record_into`]; [`crate::sysroot::run`] and the
+//! subprocess helpers in `utils` report each command to it. Commands still running when a module
+//! times out stay listed as running, which is usually the answer to "why is this slow".
+
+use serde::{Serialize, Serializer};
+use std::cell::RefCell;
+use std::ffi::OsStr;
+use std::fmt;
+use std::fmt::Write as _;
+use std::io;
+use std::process::ExitStatus;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::info::{ModuleReport, SystemInfo};
+use crate::trace::trace;
+use crate::utils::kill_process_group;
+
+pub type Commands = Arc<Mutex<Vec<SpawnedCommand>>>;
+
+thread_local! {
+    static RECORDER: RefCell<Option<Commands>> = const { RefCell::new(None) };
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ModuleTiming {
+    pub elapsed: Duration,
+    pub commands: Vec<SpawnedCommand>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SpawnedCommand {
+    pub command: String,
+    pub status: CommandStatus,
+    #[serde(rename = "elapsed_ms", serialize_with = "millis")]
+    pub elapsed: Option<Duration>,
+    #[serde(skip)]
+    pub pid: Option<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CommandStatus {
+    Running,
+    Exited(i32),
+    Killed,
+    TimedOut,
+    Failed(String),
+}
+
+/// Restores the thread's previous recorder when dropped.
+pub struct Scope {
+    previous: Option<Commands>,
+}
+
+/// Sends every command spawned on the current thread to `commands` until the scope is dropped.
+pub fn record_into(commands: Commands) -> Scope {
+    Scope { previous: RECORDER.with(|recorder| recorder.replace(Some(commands))) }
+}
+
+impl Drop for Scope {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        RECORDER.with(|recorder| *recorder.borrow_mut() = previous);
+    }
+}
+
+/// The recorder installed on the current thread, to hand on to threads a module spawns.
+#[cfg(feature = "toolchains")]
+pub(crate) fn current() -> Option<Commands> {
+    RECORDER.with(|recorder| recorder.borrow().clone())
+}
+
+/// A command that has been announced to the recorder; report its outcome with [`Spawn::finish`].
+pub(crate) struct Spawn {
+    entry: Option<(Commands, usize)>,
+    command: String,
+    started: Instant,
+}
+
+pub(crate) fn spawn<'a, I>(program: &'a OsStr, args: I) -> Spawn
+where
+    I: IntoIterator<Item = &'a OsStr>,
+{
+    let command = std::iter::once(program)
+        .chain(args)
+        .map(|part| part.to_string_lossy())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ");
+    let entry = RECORDER.with(|recorder| {
+        let commands = recorder.borrow().clone()?;
+        let mut list = commands.lock().ok()?;
+        list.push(SpawnedCommand { command: command.clone(), status: CommandStatus::Running, elapsed: None, pid: None });
+        let index = list.len() - 1;
+        drop(list);
+        Some((commands, index))
+    });
+    Spawn { entry, command, started: Instant::now() }
+}
+
+impl Spawn {
+    pub(crate) fn started(&self, pid: u32) {
+        if let Some((commands, index)) = &self.entry {
+            if let Some(command) = commands.lock().ok().as_mut().and_then(|list| list.get_mut(*index)) {
+                command.pid = Some(pid);
+            }
+        }
+    }
+
+    pub(crate) fn finish(self, status: CommandStatus) {
+        let elapsed = self.started.elapsed();
+        trace!("run `{}`: {}, {} ms", self.command, status, elapsed.as_millis());
+        if let Some((commands, index)) = self.entry {
+            if let Some(command) = commands.lock().ok().as_mut().and_then(|list| list.get_mut(index)) {
+                command.status = status;
+                command.elapsed = Some(elapsed);
+            }
+        }
+    }
+}
+
+/// Kills the commands of a module that are still running, for when it misses its deadline and
+/// gather moves on without waiting for its thread.
+pub(crate) fn kill_running(commands: &Commands) {
+    if let Ok(list) = commands.lock() {
+        for command in list.iter().filter(|command| command.status == CommandStatus::Running) {
+            if let Some(pid) = command.pid {
+                trace!("killing `{}`", command.command);
+                kill_process_group(pid);
+            }
+        }
+    }
+}
+
+impl CommandStatus {
+    pub(crate) fn spawn_error(error: &io::Error) -> Self {
+        match error.kind() {
+            io::ErrorKind::NotFound => CommandStatus::Failed("not found".to_string()),
+            _ => CommandStatus::Failed(error.to_string()),
+        }
+    }
+}
+
+impl From<ExitStatus> for CommandStatus {
+    fn from(status: ExitStatus) -> Self {
+        match status.code() {
+            Some(code) => CommandStatus::Exited(code),
+            None => CommandStatus::Killed,
+        }
+    }
+}
+
+impl fmt::Display for CommandStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CommandStatus::Running => write!(f, "running"),
+            CommandStatus::Exited(code) => write!(f, "exit {}", code),
+            CommandStatus::Killed => write!(f, "killed"),
+            CommandStatus::TimedOut => write!(f, "timed out"),
+            CommandStatus::Failed(reason) => write!(f, "failed: {}", reason),
+        }
+    }
+}
+
+impl Serialize for CommandStatus {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+fn millis<S: Serializer>(duration: &Option<Duration>, serializer: S) -> Result<S::Ok, S::Error> {
+    match duration {
+        Some(duration) => serializer.serialize_u64(duration.as_millis() as u64),
+        None => serializer.serialize_none(),
+    }
+}
+
+fn slowest_first(info: &SystemInfo) -> Vec<&ModuleReport> {
+    let mut reports: Vec<_> = info.modules.iter().collect();
+    reports.sort_by_key(|report| std::cmp::Reverse(report.timing.elapsed));
+    reports
+}
+
+/// The `_meta.timings` array of JSON output, slowest module first.
+pub fn to_json(info: &SystemInfo) -> serde_json::Value {
+    slowest_first(info)
+        .into_iter()
+        .map(|report| {
+            serde_json::json!({
+                "module": report.id(),
+                "elapsed_ms": report.timing.elapsed.as_millis() as u64,
+                "cached": report.cached.is_some(),
+                "commands": report.timing.commands,
+            })
+        })
+        .collect()
+}
+
+/// Modules sorted slowest first, each followed by the commands it spawned.
+pub fn table(info: &SystemInfo) -> String {
+    let reports = slowest_first(info);
+
+    let width = reports.iter().map(|report| report.id().len()).max().unwrap_or(0).max("Module".len());
+    let mut table = format!("{:<width$}  {:>8}  Commands\n", "Module", "Time", width = width);
+    for report in reports {
+        let time = format!("{} ms", report.timing.elapsed.as_millis());
+        let note = if report.cached.is_some() { "  (cached)" } else { "" };
+        let _ = writeln!(table, "{:<width$}  {:>8}{}", report.id(), time, note, width = width);
+        for command in &report.timing.commands {
+            let elapsed = command.elapsed.map(|d| format!(", {} ms", d.as_millis())).unwrap_or_default();
+            let _ = writeln!(
+                table,
+                "{:<width$}  {:>8}  {} ({}{})",
+                "",
+                "",
+                command.command,
+                command.status,
+                elapsed,
+                width = width
+            );
+        }
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_records_commands_on_current_thread() {
+        let commands = Commands::default();
+        {
+            let _scope = record_into(Arc::clone(&commands));
+            spawn(OsStr::new("uname"), [OsStr::new("-r")]).finish(CommandStatus::Exited(0));
+            spawn(OsStr::new("lspci"), []).finish(CommandStatus::Failed("not found".to_string()));
+        }
+        spawn(OsStr::new("ignored"), []).finish(CommandStatus::Exited(0));
+
+        let commands = commands.lock().unwrap();
+        assert_eq!(commands.len(), 2);
+        assert_eq!(commands[0].command, "uname -r");
+        assert_eq!(commands[0].status, CommandStatus::Exited(0));
+        assert_eq!(commands[1].status.to_string(), "failed: not found");
+    }
+}
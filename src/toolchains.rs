@@ -0,0 +1,203 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+use crate::config::ToolchainsConfig;
+use crate::sysroot;
+use crate::utils::{extract_version, find_in_path, output_with_timeout, spawn_scoped};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolchainInfo {
+    pub id: String,
+    pub name: String,
+    pub version: String,
+    pub path: String,
+}
+
+pub(crate) struct ToolchainProbe {
+    id: &'static str,
+    name: &'static str,
+    binary: &'static str,
+    args: &'static [&'static str],
+}
+
+const fn probe(
+    id: &'static str,
+    name: &'static str,
+    binary: &'static str,
+    args: &'static [&'static str],
+) -> ToolchainProbe {
+    ToolchainProbe { id, name, binary, args }
+}
+
+const PROBES: &[ToolchainProbe] = &[
+    probe("rust", "Rust", "rustc", &["--version"]),
+    probe("cargo", "Cargo", "cargo", &["--version"]),
+    probe("python", "Python", "python3", &["--version"]),
+    probe("node", "Node.js", "node", &["--version"]),
+    probe("go", "Go", "go", &["version"]),
+    probe("java", "Java", "java", &["-version"]),
+    probe("gcc", "GCC", "gcc", &["--version"]),
+    probe("clang", "Clang", "clang", &["--version"]),
+    probe("ruby", "Ruby", "ruby", &["--version"]),
+    probe("perl", "Perl", "perl", &["-v"]),
+    probe("php", "PHP", "php", &["--version"]),
+    probe("lua", "Lua", "lua", &["-v"]),
+    probe("deno", "Deno", "deno", &["--version"]),
+    probe("bun", "Bun", "bun", &["--version"]),
+    probe("zig", "Zig", "zig", &["version"]),
+    probe("dotnet", ".NET", "dotnet", &["--version"]),
+    probe("ghc", "GHC", "ghc", &["--numeric-version"]),
+    probe("julia", "Julia", "julia", &["--version"]),
+    probe("swift", "Swift", "swift", &["--version"]),
+];
+
+/// The configured probes whose binary is on `PATH`, in configuration order. Under a sysroot the
+/// host's `PATH` means nothing, so every probe is kept and a missing command stub rules it out.
+pub(crate) fn find_probes(config: &ToolchainsConfig) -> Vec<(&'static ToolchainProbe, PathBuf)> {
+    config
+        .probes
+        .iter()
+        .filter_map(|id| PROBES.iter().find(|probe| probe.id == id))
+        .filter_map(|probe| {
+            let path = if sysroot::is_live() { find_in_path(probe.binary)? } else { PathBuf::from(probe.binary) };
+            Some((probe, path))
+        })
+        .collect()
+}
+
+pub fn get_toolchains(config: &ToolchainsConfig) -> Vec<ToolchainInfo> {
+    let timeout = Duration::from_millis(config.timeout_ms);
+    let selected = find_probes(config);
+
+    let mut results: Vec<Option<ToolchainInfo>> = vec![None; selected.len()];
+    let (sender, receiver) = mpsc::channel();
+
+    for (index, (probe, path)) in selected.iter().enumerate() {
+        let sender = sender.clone();
+        let path = path.clone();
+        let args = probe.args;
+        spawn_scoped(move || {
+            let output = if sysroot::is_live() {
+                output_with_timeout(Command::new(&path).args(args), timeout)
+            } else {
+                sysroot::run(&path, args).ok()
+            };
+            let _ = sender.send((index, output.as_ref().and_then(version_of)));
+        });
+    }
+    drop(sender);
+
+    // Each probe enforces its own timeout; this only guards against a thread that never reports back.
+    let deadline = Instant::now() + timeout + Duration::from_millis(100);
+    let mut pending = selected.len();
+    while pending > 0 {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        match receiver.recv_timeout(remaining) {
+            Ok((index, version)) => {
+                let (probe, path) = &selected[index];
+                results[index] = version.map(|version| toolchain_info(probe, path, version));
+                pending -= 1;
+            }
+            Err(_) => break,
+        }
+    }
+
+    results.into_iter().flatten().collect()
+}
+
+/// Some tools (`java -version`, older `python --version`) print their version on stderr.
+fn version_of(output: &Output) -> Option<String> {
+    extract_version(&String::from_utf8_lossy(&output.stdout))
+        .or_else(|| extract_version(&String::from_utf8_lossy(&output.stderr)))
+}
+
+fn toolchain_info(probe: &ToolchainProbe, path: &Path, version: String) -> ToolchainInfo {
+    ToolchainInfo {
+        id: probe.id.to_string(),
+        name: probe.name.to_string(),
+        version,
+        path: path.to_string_lossy().to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::Invalidate;
+    use crate::config::Config;
+    use crate::modules;
+    use std::fs;
+    use std::time::SystemTime;
+
+    fn stubbed_root(stubs: &[(&str, &str)]) -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        let commands = dir.path().join(sysroot::COMMANDS_DIR);
+        fs::create_dir_all(&commands).unwrap();
+        for (name, stdout) in stubs {
+            fs::write(commands.join(name), stdout).unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn test_probes_follow_config_order() {
+        let root = stubbed_root(&[
+            ("go version", "go version go1.22.3 linux/amd64\n"),
+            ("rustc --version", "rustc 1.78.0 (9b00956e5 2024-04-29)\n"),
+        ]);
+        let _scope = sysroot::enter(Some(root.path().to_path_buf()));
+        let config = ToolchainsConfig {
+            probes: ["go", "nope", "node", "rust"].iter().map(|id| id.to_string()).collect(),
+            ..ToolchainsConfig::default()
+        };
+
+        let ids: Vec<&str> = find_probes(&config).iter().map(|(probe, _)| probe.id).collect();
+        assert_eq!(ids, ["go", "node", "rust"]);
+
+        // node has no stub, so it is left out.
+        let found: Vec<(String, String)> =
+            get_toolchains(&config).into_iter().map(|toolchain| (toolchain.id, toolchain.version)).collect();
+        assert_eq!(found, [("go".to_string(), "1.22.3".to_string()), ("rust".to_string(), "1.78.0".to_string())]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_version_from_stdout_or_stderr() {
+        use std::os::unix::process::ExitStatusExt;
+        use std::process::ExitStatus;
+
+        let output = |stdout: &str, stderr: &str| Output {
+            status: ExitStatus::from_raw(0),
+            stdout: stdout.as_bytes().to_vec(),
+            stderr: stderr.as_bytes().to_vec(),
+        };
+        assert_eq!(version_of(&output("Python 3.12.3\n", "")), Some("3.12.3".to_string()));
+        assert_eq!(version_of(&output("", "openjdk version \"21.0.3\" 2024-04-16\n")), Some("21.0.3".to_string()));
+        assert_eq!(version_of(&output("v20.12.2\n", "warning 1.0\n")), Some("20.12.2".to_string()));
+        assert_eq!(version_of(&output("", "")), None);
+    }
+
+    #[test]
+    fn test_cache_policy_tracks_probed_binaries() {
+        let root = stubbed_root(&[]);
+        fs::write(root.path().join("rustc"), "binary").unwrap();
+        let _scope = sysroot::enter(Some(root.path().to_path_buf()));
+
+        let mut config = Config::default();
+        config.toolchains.probes = vec!["rust".to_string(), "go".to_string()];
+        config.toolchains.cache_ttl = 600;
+        let policy = modules::find("toolchains").unwrap().cache(&config).unwrap();
+        assert_eq!(policy.ttl, Duration::from_secs(600));
+        assert!(matches!(&policy.invalidate, Invalidate::Modified(paths) if paths == &[PathBuf::from("rustc"), PathBuf::from("go")]));
+        let before = policy.invalidate.fingerprint();
+        assert!(before.ends_with(",-"));
+
+        // Upgrading a toolchain replaces its binary.
+        let binary = fs::File::options().write(true).open(root.path().join("rustc")).unwrap();
+        binary.set_modified(SystemTime::now() + Duration::from_secs(60)).unwrap();
+        assert_ne!(policy.invalidate.fingerprint(), before);
+    }
+}
@@ -0,0 +1,141 @@
+//! Debug log of what detectors looked at and decided, written to stderr (`--debug`, `RFETCH_LOG`).
+//!
+//! Files, commands and environment variables read through [`crate::sysroot`] are logged
+//! automatically; detectors add [`trace!`] lines for what they parsed and which fallback won.
+//! Each gather thread names its module with [`module`], so lines read `debug: <module>: ...`.
+//!
+//! `RFETCH_LOG` takes `1` (or `debug`, `all`) to log every module, or a comma-separated list of
+//! module ids to log only those, e.g. `RFETCH_LOG=os,terminal`.
+
+use std::cell::{Cell, RefCell};
+use std::env;
+use std::fmt;
+use std::sync::OnceLock;
+
+pub const ENV_VAR: &str = "RFETCH_LOG";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Filter {
+    Off,
+    All,
+    Modules(Vec<String>),
+}
+
+static FILTER: OnceLock<Filter> = OnceLock::new();
+
+thread_local! {
+    static MODULE: RefCell<Option<String>> = const { RefCell::new(None) };
+    static MUTED: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Turns logging on for the rest of the process. `--debug` logs every module; otherwise
+/// `RFETCH_LOG` decides. Only the first call has an effect.
+pub fn init(debug: bool) {
+    let filter = if debug { Filter::All } else { Filter::parse(&env::var(ENV_VAR).unwrap_or_default()) };
+    let _ = FILTER.set(filter);
+}
+
+impl Filter {
+    pub fn parse(value: &str) -> Self {
+        match value.trim() {
+            "" | "0" | "off" | "false" => Filter::Off,
+            "1" | "on" | "true" | "debug" | "all" | "*" => Filter::All,
+            list => Filter::Modules(
+                list.split(',').map(|id| id.trim().to_string()).filter(|id| !id.is_empty()).collect(),
+            ),
+        }
+    }
+
+    fn allows(&self, module: Option<&str>) -> bool {
+        match self {
+            Filter::Off => false,
+            Filter::All => true,
+            Filter::Modules(ids) => module.map(|module| ids.iter().any(|id| id == module)).unwrap_or(false),
+        }
+    }
+}
+
+/// Restores the thread's previous module when dropped.
+pub struct Scope {
+    previous: Option<String>,
+}
+
+/// Attributes lines logged on the current thread to module `id` until the scope is dropped.
+pub fn module(id: &str) -> Scope {
+    Scope { previous: MODULE.with(|module| module.replace(Some(id.to_string()))) }
+}
+
+/// The module lines on the current thread are attributed to.
+#[cfg(feature = "toolchains")]
+pub fn current_module() -> Option<String> {
+    MODULE.with(|module| module.borrow().clone())
+}
+
+impl Drop for Scope {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        MODULE.with(|module| *module.borrow_mut() = previous);
+    }
+}
+
+/// Unmutes the thread when dropped.
+pub struct Mute {
+    previous: bool,
+}
+
+/// Silences the current thread until the guard is dropped, for bulk scans like walking `/proc`
+/// that would otherwise log a line per file; log a summary afterwards instead.
+pub fn mute() -> Mute {
+    Mute { previous: MUTED.with(|muted| muted.replace(true)) }
+}
+
+impl Drop for Mute {
+    fn drop(&mut self) {
+        MUTED.with(|muted| muted.set(self.previous));
+    }
+}
+
+/// Whether a line logged on the current thread would be written.
+pub fn enabled() -> bool {
+    if MUTED.with(|muted| muted.get()) {
+        return false;
+    }
+    match FILTER.get() {
+        Some(Filter::Off) | None => false,
+        Some(filter) => MODULE.with(|module| filter.allows(module.borrow().as_deref())),
+    }
+}
+
+pub fn log(message: fmt::Arguments) {
+    MODULE.with(|module| match module.borrow().as_deref() {
+        Some(module) => eprintln!("debug: {}: {}", module, message),
+        None => eprintln!("debug: {}", message),
+    });
+}
+
+/// Logs a line to stderr when tracing is enabled for the current module; arguments are only
+/// formatted then.
+macro_rules! trace {
+    ($($arg:tt)*) => {
+        if $crate::trace::enabled() {
+            $crate::trace::log(format_args!($($arg)*));
+        }
+    };
+}
+
+pub(crate) use trace;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_filter() {
+        assert_eq!(Filter::parse(""), Filter::Off);
+        assert_eq!(Filter::parse("debug"), Filter::All);
+        assert_eq!(Filter::parse("os, terminal,"), Filter::Modules(vec!["os".to_string(), "terminal".to_string()]));
+        assert!(Filter::parse("os").allows(Some("os")));
+        assert!(!Filter::parse("os").allows(Some("kernel")));
+        assert!(!Filter::parse("os").allows(None));
+    }
+}
@@ -0,0 +1,248 @@
+use chrono::{DateTime, Local, TimeZone};
+use serde::{Deserialize, Serialize};
+
+use crate::sysroot;
+use crate::utils::get_username;
+
+const UTMP_PATHS: &[&str] = &["/run/utmp", "/var/run/utmp"];
+const WTMP_PATH: &str = "/var/log/wtmp";
+const LOGIND_SESSIONS: &str = "/run/systemd/sessions";
+
+// glibc `struct utmp` layout, identical on 32- and 64-bit Linux.
+const UTMP_RECORD_SIZE: usize = 384;
+const USER_PROCESS: i16 = 7;
+const UT_LINE: (usize, usize) = (8, 32);
+const UT_USER: (usize, usize) = (44, 32);
+const UT_HOST: (usize, usize) = (76, 256);
+const UT_TV_SEC: usize = 340;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsersInfo {
+    pub users: Vec<LoggedUser>,
+    pub last_login: Option<Session>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoggedUser {
+    pub name: String,
+    pub session_count: usize,
+    pub sessions: Vec<Session>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Session {
+    pub user: String,
+    pub tty: String,
+    pub host: Option<String>,
+    pub login_time: Option<DateTime<Local>>,
+}
+
+pub fn format_compact(users: &[LoggedUser]) -> String {
+    let names: Vec<&str> = users.iter().map(|user| user.name.as_str()).collect();
+    let total: usize = users.iter().map(|user| user.session_count).sum();
+
+    if total > names.len() {
+        format!("{} ({} sessions)", names.join(", "), total)
+    } else {
+        names.join(", ")
+    }
+}
+
+pub fn get_users_info() -> UsersInfo {
+    let sessions = UTMP_PATHS
+        .iter()
+        .find_map(|path| sysroot::read(path).ok())
+        .map(|data| parse_utmp(&data))
+        .filter(|sessions| !sessions.is_empty())
+        .unwrap_or_else(fallback_sessions);
+
+    let last_login = sysroot::read(WTMP_PATH)
+        .ok()
+        .and_then(|data| previous_login(&parse_utmp(&data), &sessions, &get_username()));
+
+    UsersInfo {
+        users: group_sessions(sessions),
+        last_login,
+    }
+}
+
+fn read_c_string(record: &[u8], (offset, len): (usize, usize)) -> String {
+    let field = &record[offset..offset + len];
+    let end = field.iter().position(|&b| b == 0).unwrap_or(len);
+    String::from_utf8_lossy(&field[..end]).trim().to_string()
+}
+
+pub fn parse_utmp(data: &[u8]) -> Vec<Session> {
+    data.chunks_exact(UTMP_RECORD_SIZE)
+        .filter(|record| i16::from_ne_bytes([record[0], record[1]]) == USER_PROCESS)
+        .filter_map(|record| {
+            let user = read_c_string(record, UT_USER);
+            if user.is_empty() {
+                return None;
+            }
+
+            let host = read_c_string(record, UT_HOST);
+            let seconds = i32::from_ne_bytes(record[UT_TV_SEC..UT_TV_SEC + 4].try_into().ok()?);
+
+            Some(Session {
+                user,
+                tty: read_c_string(record, UT_LINE),
+                host: (!host.is_empty()).then_some(host),
+                login_time: Local.timestamp_opt(seconds as i64, 0).single(),
+            })
+        })
+        .collect()
+}
+
+/// musl systems and some containers keep no utmp; logind still tracks sessions there.
+#[cfg(target_os = "linux")]
+fn fallback_sessions() -> Vec<Session> {
+    let mut files: Vec<_> = match sysroot::read_dir(LOGIND_SESSIONS) {
+        Ok(entries) => entries.filter_map(|entry| entry.ok()).map(|entry| entry.path()).collect(),
+        Err(_) => return Vec::new(),
+    };
+    files.sort();
+
+    files
+        .iter()
+        .filter_map(|path| std::fs::read_to_string(path).ok())
+        .filter_map(|content| parse_logind_session(&content))
+        .collect()
+}
+
+#[cfg(not(target_os = "linux"))]
+fn fallback_sessions() -> Vec<Session> {
+    sessions_from_who()
+}
+
+pub fn parse_logind_session(content: &str) -> Option<Session> {
+    let field = |key: &str| {
+        content
+            .lines()
+            .find_map(|line| line.strip_prefix(key)?.strip_prefix('='))
+            .filter(|value| !value.is_empty())
+    };
+
+    if field("CLASS").is_some_and(|class| class != "user") {
+        return None;
+    }
+
+    let realtime: Option<i64> = field("REALTIME").and_then(|micros| micros.parse().ok());
+    Some(Session {
+        user: field("USER")?.to_string(),
+        tty: field("TTY").or_else(|| field("DISPLAY")).unwrap_or("").to_string(),
+        host: field("REMOTE_HOST").map(|host| host.to_string()),
+        login_time: realtime.and_then(|micros| Local.timestamp_opt(micros / 1_000_000, 0).single()),
+    })
+}
+
+#[cfg(not(target_os = "linux"))]
+fn sessions_from_who() -> Vec<Session> {
+    let output = match sysroot::run("who", &[]) {
+        Ok(output) => output,
+        Err(_) => return Vec::new(),
+    };
+
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let user = fields.next()?.to_string();
+            let tty = fields.next().unwrap_or("").to_string();
+            let host = line
+                .rfind('(')
+                .and_then(|start| line[start + 1..].strip_suffix(')'))
+                .map(|host| host.to_string());
+            Some(Session { user, tty, host, login_time: None })
+        })
+        .collect()
+}
+
+fn group_sessions(sessions: Vec<Session>) -> Vec<LoggedUser> {
+    let mut users: Vec<LoggedUser> = Vec::new();
+
+    for session in sessions {
+        match users.iter_mut().find(|user| user.name == session.user) {
+            Some(user) => user.sessions.push(session),
+            None => users.push(LoggedUser {
+                name: session.user.clone(),
+                session_count: 0,
+                sessions: vec![session],
+            }),
+        }
+    }
+
+    for user in &mut users {
+        user.session_count = user.sessions.len();
+    }
+
+    users
+}
+
+fn previous_login(history: &[Session], active: &[Session], user: &str) -> Option<Session> {
+    history
+        .iter()
+        .rev()
+        .filter(|session| session.user == user)
+        .find(|session| !active.contains(session))
+        .cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(kind: i16, user: &str, line: &str, host: &str, seconds: i32) -> Vec<u8> {
+        let mut record = vec![0u8; UTMP_RECORD_SIZE];
+        record[0..2].copy_from_slice(&kind.to_ne_bytes());
+        record[UT_LINE.0..UT_LINE.0 + line.len()].copy_from_slice(line.as_bytes());
+        record[UT_USER.0..UT_USER.0 + user.len()].copy_from_slice(user.as_bytes());
+        record[UT_HOST.0..UT_HOST.0 + host.len()].copy_from_slice(host.as_bytes());
+        record[UT_TV_SEC..UT_TV_SEC + 4].copy_from_slice(&seconds.to_ne_bytes());
+        record
+    }
+
+    #[test]
+    fn test_parse_and_group_utmp() {
+        let mut data = record(USER_PROCESS, "alice", "tty1", "", 1_700_000_000);
+        data.extend(record(USER_PROCESS, "alice", "pts/0", "10.0.0.5", 1_700_000_100));
+        data.extend(record(USER_PROCESS, "bob", "pts/1", "", 1_700_000_200));
+        data.extend(record(8, "", "pts/2", "", 1_700_000_300));
+
+        let sessions = parse_utmp(&data);
+        assert_eq!(sessions.len(), 3);
+        assert_eq!(sessions[1].host.as_deref(), Some("10.0.0.5"));
+
+        let users = group_sessions(sessions);
+        assert_eq!(users.len(), 2);
+        assert_eq!(users[0].session_count, 2);
+        assert_eq!(format_compact(&users), "alice, bob (3 sessions)");
+    }
+
+    #[test]
+    fn test_previous_login_skips_active_sessions() {
+        let history = parse_utmp(&[
+            record(USER_PROCESS, "alice", "pts/0", "old-host", 1_600_000_000),
+            record(USER_PROCESS, "alice", "pts/0", "", 1_700_000_000),
+        ].concat());
+        let active = vec![history[1].clone()];
+
+        let last = previous_login(&history, &active, "alice").unwrap();
+        assert_eq!(last.host.as_deref(), Some("old-host"));
+    }
+
+    #[test]
+    fn test_parse_logind_session() {
+        let session = parse_logind_session(
+            "# This is private data. Do not parse.\nUID=1000\nUSER=alice\nACTIVE=1\nTTY=tty2\n\
+             REMOTE_HOST=10.0.0.5\nCLASS=user\nREALTIME=1700000000123456\n",
+        )
+        .unwrap();
+        assert_eq!(session.user, "alice");
+        assert_eq!(session.tty, "tty2");
+        assert_eq!(session.host.as_deref(), Some("10.0.0.5"));
+        assert_eq!(session.login_time.map(|time| time.timestamp()), Some(1_700_000_000));
+
+        assert!(parse_logind_session("USER=gdm\nCLASS=greeter\n").is_none());
+    }
+}
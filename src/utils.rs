@@ -1,21 +1,27 @@
 use std::env;
 use std::fs;
-use std::path::Path;
-use std::process::Command;
-
-#[cfg(unix)]
-use std::os::unix::fs::MetadataExt;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::cache;
+use crate::process;
+use crate::sysroot;
+use crate::timings::{self, CommandStatus};
+use crate::trace::trace;
 
 pub fn get_username() -> String {
-    env::var("USER")
-        .or_else(|_| env::var("USERNAME"))
+    sysroot::var("USER")
+        .or_else(|_| sysroot::var("USERNAME"))
         .unwrap_or_else(|_| "unknown".to_string())
 }
 
 pub fn get_hostname() -> String {
-    if let Ok(hostname) = fs::read_to_string("/proc/sys/kernel/hostname") {
+    if let Ok(hostname) = sysroot::read_to_string("/proc/sys/kernel/hostname") {
         hostname.trim().to_string()
-    } else if let Ok(output) = Command::new("hostname").output() {
+    } else if let Ok(output) = sysroot::run("hostname", &[]) {
         String::from_utf8_lossy(&output.stdout).trim().to_string()
     } else {
         "unknown".to_string()
@@ -53,65 +59,546 @@ pub fn format_uptime(uptime_seconds: u64) -> String {
     }
 }
 
+const KNOWN_SHELLS: &[&str] = &[
+    "bash", "zsh", "fish", "sh", "dash", "ash", "ksh", "mksh", "oksh", "loksh",
+    "tcsh", "csh", "nu", "elvish", "xonsh", "pwsh", "ion", "yash", "osh",
+];
+
+const SHELL_VERSION_RULES: &[(&str, &[&str])] = &[
+    ("bash", &["--version"]),
+    ("zsh", &["--version"]),
+    ("fish", &["--version"]),
+    ("ksh", &["--version"]),
+    ("tcsh", &["--version"]),
+    ("nu", &["--version"]),
+    ("elvish", &["-version"]),
+    ("xonsh", &["--version"]),
+    ("pwsh", &["--version"]),
+    ("ion", &["--version"]),
+    ("yash", &["--version"]),
+    ("osh", &["--version"]),
+];
+
+const SHELL_VERSION_VARS: &[(&str, &str)] = &[
+    ("bash", "BASH_VERSION"),
+    ("zsh", "ZSH_VERSION"),
+    ("fish", "FISH_VERSION"),
+    ("nu", "NU_VERSION"),
+];
+
+/// Version strings compiled into shell binaries, e.g. bash's `@(#)Bash version 5.2.15(1) release`,
+/// so the version can be read from the binary instead of running it.
+const SHELL_VERSION_MARKERS: &[(&str, &str)] = &[("bash", "@(#)Bash version ")];
+
+const TERMINAL_WRAPPERS: &[&str] = &[
+    "sudo", "su", "doas", "env", "nohup", "script", "strace", "ltrace", "time",
+    "watch", "nix-shell", "direnv", "make", "cargo",
+];
+
+const TERMINAL_VERSION_RULES: &[(&str, &str, &[&str])] = &[
+    ("kitty", "kitty", &["--version"]),
+    ("alacritty", "alacritty", &["--version"]),
+    ("wezterm", "wezterm", &["--version"]),
+    ("foot", "foot", &["--version"]),
+    ("ghostty", "ghostty", &["--version"]),
+    ("konsole", "konsole", &["--version"]),
+    ("gnome-terminal", "gnome-terminal", &["--version"]),
+    ("xfce4-terminal", "xfce4-terminal", &["--version"]),
+    ("tilix", "tilix", &["--version"]),
+    ("terminator", "terminator", &["--version"]),
+    ("lxterminal", "lxterminal", &["--version"]),
+    ("mate-terminal", "mate-terminal", &["--version"]),
+    ("qterminal", "qterminal", &["--version"]),
+    ("terminology", "terminology", &["--version"]),
+    ("rio", "rio", &["--version"]),
+    ("contour", "contour", &["version"]),
+    ("urxvt", "urxvt", &[]),
+    ("st", "st", &["-v"]),
+    ("xterm", "xterm", &["-version"]),
+    ("tmux", "tmux", &["-V"]),
+    ("screen", "screen", &["--version"]),
+    ("zellij", "zellij", &["--version"]),
+];
+
+lazy_static::lazy_static! {
+    static ref DOTTED_VERSION_REGEX: regex::Regex = regex::Regex::new(r"\d+(?:\.\d+)+").unwrap();
+    static ref PLAIN_VERSION_REGEX: regex::Regex = regex::Regex::new(r"\d{3,}").unwrap();
+}
+
+pub fn extract_version(output: &str) -> Option<String> {
+    DOTTED_VERSION_REGEX
+        .find(output)
+        .or_else(|| PLAIN_VERSION_REGEX.find(output))
+        .map(|m| m.as_str().to_string())
+}
+
+pub fn output_with_timeout(command: &mut Command, timeout: Duration) -> Option<Output> {
+    output_with_input(command, None, timeout)
+}
+
+pub fn output_with_input(command: &mut Command, input: Option<&[u8]>, timeout: Duration) -> Option<Output> {
+    let spawn = timings::spawn(command.get_program(), command.get_args());
+    // A group of its own lets a timeout kill whatever the command started too, e.g. under `sh -c`.
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(command, 0);
+    let mut child = match command
+        .stdin(if input.is_some() { Stdio::piped() } else { Stdio::null() })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+    {
+        Ok(child) => child,
+        Err(e) => {
+            spawn.finish(CommandStatus::spawn_error(&e));
+            return None;
+        }
+    };
+    spawn.started(child.id());
+
+    if let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) {
+        let input = input.to_vec();
+        thread::spawn(move || {
+            let _ = stdin.write_all(&input);
+        });
+    }
+
+    // Drain the pipes while waiting so a chatty child cannot block on a full pipe buffer.
+    let stdout = child.stdout.take().map(read_in_background);
+    let stderr = child.stderr.take().map(read_in_background);
+    let deadline = Instant::now() + sysroot::remaining(timeout);
+
+    loop {
+        match child.try_wait() {
+            Ok(Some(status)) => {
+                let collect = |reader: Option<thread::JoinHandle<Vec<u8>>>| {
+                    reader.and_then(|reader| reader.join().ok()).unwrap_or_default()
+                };
+                spawn.finish(status.into());
+                return Some(Output { status, stdout: collect(stdout), stderr: collect(stderr) });
+            }
+            Ok(None) if Instant::now() < deadline => thread::sleep(Duration::from_millis(5)),
+            _ => {
+                kill_process_group(child.id());
+                let _ = child.kill();
+                let _ = child.wait();
+                spawn.finish(CommandStatus::TimedOut);
+                return None;
+            }
+        }
+    }
+}
+
+/// Runs `work` on a new thread that keeps the caller's sysroot, deadline, timings recorder and
+/// trace module, so commands a module runs in parallel stay stubbed, bounded, listed and logged.
+#[cfg(feature = "toolchains")]
+pub(crate) fn spawn_scoped<F>(work: F)
+where
+    F: FnOnce() + Send + 'static,
+{
+    let root = sysroot::current();
+    let deadline = sysroot::current_deadline();
+    let commands = timings::current();
+    let module = crate::trace::current_module();
+    thread::spawn(move || {
+        let _sysroot = sysroot::enter(root);
+        let _deadline = deadline.map(sysroot::deadline);
+        let _timings = commands.map(timings::record_into);
+        let _trace = module.as_deref().map(crate::trace::module);
+        work();
+    });
+}
+
+/// Kills the process group led by `pid`, as started by [`output_with_input`]. Elsewhere only the
+/// child itself can be killed, through its handle.
+#[cfg(unix)]
+pub(crate) fn kill_process_group(pid: u32) {
+    use nix::sys::signal::{killpg, Signal};
+    use nix::unistd::Pid;
+    let _ = killpg(Pid::from_raw(pid as i32), Signal::SIGKILL);
+}
+
+#[cfg(not(unix))]
+pub(crate) fn kill_process_group(_pid: u32) {}
+
+fn read_in_background<R: Read + Send + 'static>(mut reader: R) -> thread::JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut buffer = Vec::new();
+        let _ = reader.read_to_end(&mut buffer);
+        buffer
+    })
+}
+
+pub fn find_in_path(program: &str) -> Option<std::path::PathBuf> {
+    let paths = env::var_os("PATH")?;
+    let name = if cfg!(windows) { format!("{}.exe", program) } else { program.to_string() };
+    env::split_paths(&paths)
+        .map(|dir| dir.join(&name))
+        .find(|candidate| is_executable(candidate))
+}
+
+#[cfg(unix)]
+pub fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    fs::metadata(path)
+        .map(|meta| meta.is_file() && meta.permissions().mode() & 0o111 != 0)
+        .unwrap_or(false)
+}
+
+#[cfg(windows)]
+pub fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+/// Runs `program args` for its version, or reuses the one found last run if the binary is
+/// unchanged (see [`cache::version`]).
+pub fn probe_version<S: AsRef<std::ffi::OsStr>>(program: S, args: &[&str]) -> Option<String> {
+    let program = program.as_ref();
+    cache::version(program, args, || {
+        let output = sysroot::run(program, args).ok()?;
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+
+        extract_version(&stdout).or_else(|| extract_version(&stderr))
+    })
+}
+
+pub fn with_version(name: &str, version: Option<String>) -> String {
+    match version {
+        Some(version) => format!("{} {}", name, version),
+        None => name.to_string(),
+    }
+}
+
+fn is_shell(name: &str) -> bool {
+    KNOWN_SHELLS.contains(&name)
+}
+
+fn shell_version(name: &str, exe: Option<&Path>) -> Option<String> {
+    // Only set when the user exported it, but then nothing needs to run.
+    if let Some((_, var)) = SHELL_VERSION_VARS.iter().find(|(shell, _)| *shell == name) {
+        if let Some(version) = sysroot::var(var).ok().and_then(|value| extract_version(&value)) {
+            return Some(version);
+        }
+    }
+
+    if let Some((_, marker)) = SHELL_VERSION_MARKERS.iter().find(|(shell, _)| *shell == name) {
+        let binary = exe.map(Path::to_path_buf).or_else(|| find_in_path(name));
+        if let Some(version) = binary.and_then(|binary| embedded_version(&binary, marker)) {
+            return Some(version);
+        }
+    }
+
+    let (_, args) = SHELL_VERSION_RULES.iter().find(|(shell, _)| *shell == name)?;
+    match exe {
+        Some(exe) => probe_version(exe, args),
+        None => probe_version(name, args),
+    }
+}
+
+/// The version following `marker` in `binary`, up to the end of that C string.
+fn embedded_version(binary: &Path, marker: &str) -> Option<String> {
+    let data = sysroot::read(binary).ok()?;
+    let start = data.windows(marker.len()).position(|window| window == marker.as_bytes())? + marker.len();
+    let end = data[start..].iter().position(|&byte| byte == 0).map_or(data.len(), |len| start + len);
+    let version = extract_version(&String::from_utf8_lossy(&data[start..end]));
+    if let Some(version) = &version {
+        trace!("version {} embedded in {}", version, binary.display());
+    }
+    version
+}
+
+fn normalize_terminal_name(name: &str) -> String {
+    let name = name.split(':').next().unwrap_or(name).trim();
+    let name = name.trim_start_matches('.');
+    let name = name.strip_suffix("-wrapped").unwrap_or(name);
+    let name = name.strip_suffix("-server").unwrap_or(name);
+    let name = name.strip_suffix("-gui").unwrap_or(name);
+    name.to_string()
+}
+
+fn terminal_version(name: &str, exe: Option<&Path>) -> Option<String> {
+    if let (Ok(program), Ok(version)) = (sysroot::var("TERM_PROGRAM"), sysroot::var("TERM_PROGRAM_VERSION")) {
+        if normalize_terminal_name(&program.to_lowercase()) == name {
+            return Some(version);
+        }
+    }
+
+    let (_, binary, args) = TERMINAL_VERSION_RULES.iter().find(|(terminal, _, _)| *terminal == name)?;
+    if args.is_empty() {
+        return None;
+    }
+
+    // Helper executables such as gnome-terminal-server do not answer --version.
+    match exe {
+        Some(exe) if exe.file_name().and_then(|n| n.to_str()) == Some(*binary) => probe_version(exe, args),
+        _ => probe_version(binary, args),
+    }
+}
+
+pub fn format_uptime_long(uptime_seconds: u64) -> String {
+    let units = [
+        (uptime_seconds / 86400, "day"),
+        ((uptime_seconds % 86400) / 3600, "hour"),
+        ((uptime_seconds % 3600) / 60, "minute"),
+    ];
+
+    let parts: Vec<String> = units
+        .iter()
+        .filter(|(value, _)| *value > 0)
+        .map(|(value, unit)| format!("{} {}{}", value, unit, if *value == 1 { "" } else { "s" }))
+        .collect();
+
+    if parts.is_empty() {
+        "0 minutes".to_string()
+    } else {
+        parts.join(", ")
+    }
+}
+
+pub fn format_uptime_template(uptime_seconds: u64, template: &str) -> String {
+    template
+        .replace("{days}", &(uptime_seconds / 86400).to_string())
+        .replace("{hours}", &((uptime_seconds % 86400) / 3600).to_string())
+        .replace("{minutes}", &((uptime_seconds % 3600) / 60).to_string())
+        .replace("{seconds}", &(uptime_seconds % 60).to_string())
+        .replace("{total_hours}", &(uptime_seconds / 3600).to_string())
+}
+
 pub fn get_shell() -> String {
-    env::var("SHELL")
+    let chain = process::parent_chain();
+    if let Some(shell) = chain.iter().find(|p| is_shell(&p.name())) {
+        let name = shell.name();
+        trace!("using parent process {} ({})", shell.pid, name);
+        return with_version(&name, shell_version(&name, shell.exe.as_deref()));
+    }
+
+    trace!("no shell among parent processes, falling back to $SHELL");
+    sysroot::var("SHELL")
         .map(|shell| {
-            Path::new(&shell)
+            let name = Path::new(&shell)
                 .file_name()
                 .and_then(|name| name.to_str())
                 .unwrap_or(&shell)
-                .to_string()
+                .to_string();
+            with_version(&name, shell_version(&name, Some(Path::new(&shell))))
         })
         .unwrap_or_else(|_| "unknown".to_string())
 }
 
-pub fn get_terminal() -> String {
-    if let Ok(term) = env::var("TERM_PROGRAM") {
-        return term;
+fn get_terminal_from_process_tree() -> Option<String> {
+    let chain = process::parent_chain();
+    let shell_index = chain.iter().position(|p| is_shell(&p.name()))?;
+
+    for process in &chain[shell_index + 1..] {
+        let name = normalize_terminal_name(&process.name());
+
+        if is_shell(&name) || TERMINAL_WRAPPERS.contains(&name.as_str()) {
+            continue;
+        }
+
+        trace!("terminal candidate: parent process {} ({})", process.pid, name);
+        match name.as_str() {
+            "systemd" | "init" | "launchd" => return None,
+            "login" | "agetty" | "getty" => return Some("Linux console".to_string()),
+            _ if name.starts_with("sshd") => return Some("SSH".to_string()),
+            _ => {}
+        }
+
+        return Some(with_version(&name, terminal_version(&name, process.exe.as_deref())));
     }
 
+    None
+}
+
+pub fn get_terminal() -> String {
     if is_termux() {
+        trace!("$PREFIX points into Termux");
         return "Termux".to_string();
     }
 
-    if let Ok(term) = env::var("TERM") {
+    if let Some(terminal) = get_terminal_from_process_tree() {
+        return terminal;
+    }
+    trace!("no terminal in the process tree, falling back to environment variables");
+
+    if let Ok(term) = sysroot::var("TERM_PROGRAM") {
+        return with_version(&term, sysroot::var("TERM_PROGRAM_VERSION").ok());
+    }
+
+    if let Ok(term) = sysroot::var("TERM") {
         if !term.is_empty() && term != "xterm" && term != "xterm-256color" {
             return term;
         }
+        trace!("ignoring generic $TERM {:?}", term);
     }
 
-    if let Ok(term) = env::var("TERMINAL") {
+    if let Ok(term) = sysroot::var("TERMINAL") {
         return term;
     }
 
-    if let Ok(term) = env::var("COLORTERM") {
+    if let Ok(term) = sysroot::var("COLORTERM") {
         return term;
     }
 
-    if let Ok(term) = env::var("TERM_PROGRAM_VERSION") {
-        return format!("Terminal {}", term);
+    "unknown".to_string()
+}
+
+pub fn is_termux() -> bool {
+    sysroot::var("PREFIX").map(|p| p.contains("com.termux")).unwrap_or(false)
+}
+
+/// How to count the packages in one database.
+#[cfg(target_os = "linux")]
+enum Count {
+    /// Directories exactly this many levels below the path, e.g. `<id>/<arch>/<branch>` refs.
+    Dirs(usize),
+    /// Entries of a dpkg `status` file marked installed.
+    Dpkg,
+    /// Lines of a file matching a predicate.
+    Lines(fn(&str) -> bool),
+    /// Mounted snaps, one directory each next to `bin`.
+    Snaps,
+}
+
+/// Package databases rfetch reads itself, and how. `~/` is the user's home directory. Together
+/// with [`RPM_SQLITE`] and [`SPAWNED_PACKAGE_MANAGERS`] this is everything the Packages count
+/// depends on, so it also drives the cache invalidation (see [`package_databases`]).
+#[cfg(target_os = "linux")]
+const PACKAGE_SOURCES: &[(&str, Count)] = &[
+    ("/var/lib/pacman/local", Count::Dirs(1)),
+    ("/var/lib/dpkg/status", Count::Dpkg),
+    ("/data/data/com.termux/files/usr/var/lib/dpkg/status", Count::Dpkg),
+    ("/lib/apk/db/installed", Count::Lines(|line| line.starts_with("P:"))),
+    ("/var/db/xbps/pkgdb-0.38.plist", Count::Lines(|line| line.trim() == "<string>installed</string>")),
+    ("/var/db/pkg", Count::Dirs(2)),
+    ("/var/lib/flatpak/app", Count::Dirs(3)),
+    ("/var/lib/flatpak/runtime", Count::Dirs(3)),
+    ("~/.local/share/flatpak/app", Count::Dirs(3)),
+    ("~/.local/share/flatpak/runtime", Count::Dirs(3)),
+    ("/snap", Count::Snaps),
+    ("/home/linuxbrew/.linuxbrew/Cellar", Count::Dirs(1)),
+];
+
+/// rpm's SQLite database, in lookup order; `/var/lib/rpm` is often a link to the second.
+#[cfg(target_os = "linux")]
+pub const RPM_SQLITE: &[&str] = &["/var/lib/rpm/rpmdb.sqlite", "/usr/lib/sysimage/rpm/rpmdb.sqlite"];
+
+/// Package managers whose databases rfetch cannot read: the program, its listing arguments and
+/// the database paths whose presence means it is worth running. These are the only commands a
+/// package count spawns: rpm only when its database is not a SQLite file rfetch could read
+/// (Berkeley DB, ndb), nix-env and guix.
+#[cfg(target_os = "linux")]
+pub const SPAWNED_PACKAGE_MANAGERS: &[(&str, &[&str], &[&str])] = &[
+    ("rpm", &["-qa"], &["/var/lib/rpm", "/usr/lib/sysimage/rpm"]),
+    ("nix-env", &["-q"], &["/nix/var/nix/profiles"]),
+    ("guix", &["package", "--list-installed"], &["/var/guix/profiles"]),
+];
+
+/// Every file or directory [`count_packages`] reads or checks, so the Packages cache goes stale
+/// when any of them changes.
+#[cfg(target_os = "linux")]
+pub fn package_databases() -> Vec<PathBuf> {
+    PACKAGE_SOURCES
+        .iter()
+        .filter_map(|(path, _)| expand_home(path))
+        .chain(RPM_SQLITE.iter().map(PathBuf::from))
+        .chain(SPAWNED_PACKAGE_MANAGERS.iter().flat_map(|(_, _, databases)| databases.iter().map(PathBuf::from)))
+        .collect()
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn package_databases() -> Vec<PathBuf> {
+    ["/usr/local/Cellar", "/opt/homebrew/Cellar", "/var/db/pkg"].iter().map(PathBuf::from).collect()
+}
+
+#[cfg(target_os = "linux")]
+fn expand_home(path: &str) -> Option<PathBuf> {
+    match path.strip_prefix("~/") {
+        Some(rest) => dirs::home_dir().map(|home| home.join(rest)),
+        None => Some(PathBuf::from(path)),
     }
+}
 
-    if let Ok(output) = Command::new("ps")
-        .args(&["-o", "comm=", "-p"])
-        .arg(format!("{}", std::process::id()))
-        .output()
-    {
-        if let Ok(parent) = String::from_utf8(output.stdout) {
-            let parent = parent.trim();
-            if !parent.is_empty() {
-                return parent.to_string();
-            }
-        }
+/// Counts installed packages by reading each package manager's database. Only rpm without a
+/// SQLite database, nix and guix are asked through their command line.
+#[cfg(target_os = "linux")]
+pub fn count_packages() -> u64 {
+    let rpm = count_rpm_sqlite();
+    let native: u64 = PACKAGE_SOURCES
+        .iter()
+        .filter_map(|(path, count)| {
+            let path = expand_home(path)?;
+            Some(match count {
+                Count::Dirs(depth) => count_dirs(&path, *depth),
+                Count::Dpkg => count_lines(&path, |line| line.starts_with("Status:") && line.ends_with(" installed")),
+                Count::Lines(matches) => count_lines(&path, *matches),
+                Count::Snaps => count_dirs(&path, 1).saturating_sub(sysroot::exists(path.join("bin")) as u64),
+            })
+        })
+        .sum();
+
+    let spawned: u64 = SPAWNED_PACKAGE_MANAGERS
+        .iter()
+        .filter(|(manager, _, _)| !(*manager == "rpm" && rpm.is_some()))
+        .filter(|(_, _, databases)| databases.iter().any(sysroot::exists))
+        .filter_map(|(manager, args, _)| sysroot::run(manager, args).ok())
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).lines().filter(|line| !line.trim().is_empty()).count() as u64)
+        .sum();
+    if spawned > 0 {
+        trace!("{} packages listed by rpm, nix-env or guix", spawned);
     }
 
-    "unknown".to_string()
+    native + rpm.unwrap_or(0) + spawned
 }
 
-pub fn is_termux() -> bool {
-    env::var("PREFIX").map(|p| p.contains("com.termux")).unwrap_or(false)
+/// Packages in rpm's SQLite database, or `None` without one rfetch can read.
+#[cfg(target_os = "linux")]
+pub fn count_rpm_sqlite() -> Option<u64> {
+    let (path, count) = RPM_SQLITE
+        .iter()
+        .find_map(|path| sysroot::read(path).ok().map(|data| (path, crate::sqlite::count_rows(&data, "Packages"))))?;
+    match count {
+        Some(count) => trace!("{} packages in {}", count, path),
+        None => trace!("{} is not a readable SQLite database", path),
+    }
+    count
+}
+
+/// Counts directories exactly `depth` levels below `root`, e.g. `<id>/<arch>/<branch>` refs.
+#[cfg(target_os = "linux")]
+fn count_dirs<P: AsRef<Path>>(root: P, depth: usize) -> u64 {
+    fn walk(dir: &Path, depth: usize) -> u64 {
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(_) => return 0,
+        };
+        entries
+            .filter_map(|entry| entry.ok())
+            // Skip symlinks such as flatpak's `current`, which point back into the same tree.
+            .filter(|entry| entry.file_type().is_ok_and(|kind| kind.is_dir()))
+            .map(|entry| if depth <= 1 { 1 } else { walk(&entry.path(), depth - 1) })
+            .sum()
+    }
+    let count = walk(&sysroot::path(&root), depth);
+    if count > 0 {
+        trace!("{} entries in {}", count, root.as_ref().display());
+    }
+    count
 }
 
+#[cfg(target_os = "linux")]
+fn count_lines(file: &Path, matches: fn(&str) -> bool) -> u64 {
+    let count = sysroot::read_to_string(file)
+        .map(|content| content.lines().filter(|line| matches(line)).count() as u64)
+        .unwrap_or(0);
+    if count > 0 {
+        trace!("{} packages in {}", count, file.display());
+    }
+    count
+}
+
+#[cfg(not(target_os = "linux"))]
 pub fn count_packages() -> u64 {
     let mut total = 0;
 
@@ -136,7 +623,7 @@ pub fn count_packages() -> u64 {
     ];
 
     for (manager, args) in &package_managers {
-        if let Ok(output) = Command::new(manager).args(*args).output() {
+        if let Ok(output) = sysroot::run(manager, args) {
             if output.status.success() {
                 let count = String::from_utf8_lossy(&output.stdout)
                     .lines()
@@ -155,119 +642,113 @@ pub fn count_packages() -> u64 {
     total
 }
 
-pub fn is_container() -> bool {
-    if std::path::Path::new("/.dockerenv").exists() {
-        return true;
-    }
-
-    if let Ok(content) = std::fs::read_to_string("/proc/1/cgroup") {
-        if content.contains("docker") || content.contains("lxc") || content.contains("containerd") {
-            return true;
-        }
-    }
-
-    false
+/// The candidate closest to a mistyped `name`, if it is close enough to be what was meant.
+pub fn suggest<'a, I>(name: &str, candidates: I) -> Option<&'a str>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let limit = name.chars().count().div_ceil(3).max(1);
+    candidates
+        .into_iter()
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .filter(|(distance, _)| *distance <= limit)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
 }
 
-pub fn get_desktop_environment() -> String {
-    let de_vars = [
-        "XDG_CURRENT_DESKTOP",
-        "DESKTOP_SESSION", 
-        "GDMSESSION",
-        "KDE_SESSION_VERSION",
-        "GNOME_DESKTOP_SESSION_ID",
-    ];
-
-    for var in &de_vars {
-        if let Ok(value) = env::var(var) {
-            if !value.is_empty() {
-                return value.to_lowercase();
-            }
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != *cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
         }
     }
-
-    let de_processes = [
-        "gnome-session",
-        "kde-session", 
-        "xfce4-session",
-        "lxsession",
-        "mate-session",
-        "cinnamon-session",
-    ];
-
-    for process in &de_processes {
-        if Command::new("pgrep").arg(process).output().map(|o| o.status.success()).unwrap_or(false) {
-            return process.replace("-session", "").to_string();
-        }
-    }
-
-    "unknown".to_string()
+    row[b.len()]
 }
 
-pub fn get_window_manager() -> String {
-    let wm_processes = [
-        "i3", "sway", "bspwm", "dwm", "awesome", "xmonad", 
-        "openbox", "fluxbox", "blackbox", "fvwm", "jwm",
-        "herbstluftwm", "qtile", "spectrwm", "cwm", "2bwm",
-    ];
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    for wm in &wm_processes {
-        if Command::new("pgrep").arg(wm).output().map(|o| o.status.success()).unwrap_or(false) {
-            return wm.to_string();
-        }
+    #[test]
+    fn test_format_uptime_long() {
+        assert_eq!(format_uptime_long(3 * 86400 + 4 * 3600 + 60), "3 days, 4 hours, 1 minute");
+        assert_eq!(format_uptime_long(30), "0 minutes");
     }
 
-    if let Ok(wm) = env::var("WINDOW_MANAGER") {
-        return wm;
+    #[test]
+    fn test_format_uptime_template() {
+        assert_eq!(format_uptime_template(93784, "{days}d {hours}:{minutes}:{seconds}"), "1d 2:3:4");
+        assert_eq!(format_uptime_template(93784, "{total_hours}h"), "26h");
     }
 
-    "unknown".to_string()
-}
-
-#[cfg(unix)]
-pub fn get_file_count(path: &str) -> u64 {
-    if let Ok(entries) = fs::read_dir(path) {
-        entries.filter_map(|entry| entry.ok()).count() as u64
-    } else {
-        0
+    #[test]
+    fn test_extract_version() {
+        assert_eq!(extract_version("GNU bash, version 5.2.15(1)-release").as_deref(), Some("5.2.15"));
+        assert_eq!(extract_version("wezterm 20240203-110809-5046fc22").as_deref(), Some("20240203"));
+        assert_eq!(extract_version("no version here"), None);
     }
-}
 
-#[cfg(windows)]
-pub fn get_file_count(path: &str) -> u64 {
-    if let Ok(entries) = fs::read_dir(path) {
-        entries.filter_map(|entry| entry.ok()).count() as u64
-    } else {
-        0
+    #[test]
+    fn test_embedded_version() {
+        let dir = tempfile::tempdir().unwrap();
+        let binary = dir.path().join("bash");
+        fs::write(&binary, b"\x7fELF\0GNU bash, version %s\0@(#)Bash version 5.2.26(1) release GNU\0").unwrap();
+        assert_eq!(embedded_version(&binary, "@(#)Bash version ").as_deref(), Some("5.2.26"));
+        assert_eq!(embedded_version(&binary, "zsh-"), None);
     }
-}
 
-pub fn get_cpu_cores() -> usize {
-    num_cpus::get()
-}
+    /// rpm is the only package manager a Fedora-like system could need, and it is run only when
+    /// its database is not SQLite; `rpm -qa` has no stub here, so running it would count nothing.
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_count_packages_reads_rpm_sqlite() {
+        let fixture = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/fedora/var/lib/rpm/rpmdb.sqlite");
+        let root = tempfile::tempdir().unwrap();
+        fs::create_dir_all(root.path().join("var/lib/rpm")).unwrap();
+        fs::copy(&fixture, root.path().join("var/lib/rpm/rpmdb.sqlite")).unwrap();
+        let _scope = sysroot::enter(Some(root.path().to_path_buf()));
+        assert_eq!(count_packages(), 4);
+
+        // A Berkeley DB database is listed by rpm instead.
+        fs::remove_file(root.path().join("var/lib/rpm/rpmdb.sqlite")).unwrap();
+        fs::write(root.path().join("var/lib/rpm/Packages"), "").unwrap();
+        fs::create_dir_all(root.path().join(sysroot::COMMANDS_DIR)).unwrap();
+        fs::write(root.path().join(sysroot::COMMANDS_DIR).join("rpm -qa"), "bash-5.2.26-3.fc40.x86_64\n").unwrap();
+        assert_eq!(count_packages(), 1);
+    }
 
-pub fn truncate_string(s: &str, max_len: usize) -> String {
-    if s.len() <= max_len {
-        s.to_string()
-    } else {
-        format!("{}...", &s[..max_len.saturating_sub(3)])
+    /// Installing anything that changes the count must also invalidate the cached Packages value.
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_package_databases_track_counted_sources() {
+        let root = tempfile::tempdir().unwrap();
+        for dir in ["var/lib/flatpak/runtime", "home/linuxbrew/.linuxbrew/Cellar"] {
+            fs::create_dir_all(root.path().join(dir)).unwrap();
+        }
+        let _scope = sysroot::enter(Some(root.path().to_path_buf()));
+        let trigger = cache::Invalidate::Modified(package_databases());
+
+        for dir in ["var/lib/flatpak/runtime/org.gnome.Platform/x86_64/46", "home/linuxbrew/.linuxbrew/Cellar/jq"] {
+            let before = trigger.fingerprint();
+            let count = count_packages();
+            fs::create_dir_all(root.path().join(dir)).unwrap();
+            assert_eq!(count_packages(), count + 1);
+            assert_ne!(trigger.fingerprint(), before, "{} is not watched", dir);
+        }
     }
-}
 
-pub fn center_text(text: &str, width: usize) -> String {
-    let text_len = text.len();
-    if text_len >= width {
-        return text.to_string();
+    #[test]
+    fn test_suggest() {
+        let modules = ["os", "kernel", "memory", "local_ip"];
+        assert_eq!(suggest("kernal", modules), Some("kernel"));
+        assert_eq!(suggest("localip", modules), Some("local_ip"));
+        assert_eq!(suggest("gpu", modules), None);
+        assert_eq!(suggest("neno", ["default", "neon", "minimal", "retro"]), Some("neon"));
     }
-    
-    let padding = (width - text_len) / 2;
-    format!("{}{}", " ".repeat(padding), text)
 }
-
-pub fn is_running_in_container() -> bool {
-    Path::new("/.dockerenv").exists() ||
-    env::var("container").is_ok() ||
-    fs::read_to_string("/proc/1/cgroup")
-        .map(|content| content.contains("docker") || content.contains("lxc"))
-        .unwrap_or(false)
-}
\ No newline at end of file
@@ -0,0 +1,73 @@
+//! Golden-output tests: gather from each captured system under `tests/fixtures/<name>` and compare
+//! the rendered text with `tests/fixtures/<name>.txt`. Run with `UPDATE_SNAPSHOTS=1` to rewrite.
+
+#![cfg(all(feature = "sensors", feature = "network"))]
+
+use std::fs;
+use std::path::Path;
+
+use rfetch::Config;
+
+/// Modules whose output depends only on the sysroot, not on environment variables or the clock.
+const GOLDEN_MODULES: &[&str] = &[
+    "os", "kernel", "security", "uptime", "packages", "cpu", "gpu", "memory", "disk", "battery", "sensors",
+    "local_ip",
+];
+
+fn render_fixture(name: &str) -> String {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures").join(name);
+
+    let mut config = Config::default();
+    for module in rfetch::cli::modules::MODULES.iter() {
+        config.info.set_enabled(module.id(), GOLDEN_MODULES.contains(&module.id()));
+    }
+    config.display.color_mode = "never".to_string();
+    config.display.logo_type = "small".to_string();
+    config.plugins.enabled = false;
+    config.sysroot = Some(root);
+
+    let mut info = rfetch::gather(&config);
+    info.user = "user".to_string();
+    rfetch::render(&info, &config, None).unwrap()
+}
+
+fn assert_golden(name: &str) {
+    let actual = render_fixture(name);
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures").join(format!("{}.txt", name));
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        fs::write(&path, &actual).unwrap();
+        return;
+    }
+    let expected = fs::read_to_string(&path).unwrap_or_default();
+    assert_eq!(actual, expected, "golden output for {} differs", name);
+}
+
+#[test]
+fn golden_arch() {
+    assert_golden("arch");
+}
+
+#[test]
+fn golden_debian() {
+    assert_golden("debian");
+}
+
+#[test]
+fn golden_fedora() {
+    assert_golden("fedora");
+}
+
+#[test]
+fn golden_alpine() {
+    assert_golden("alpine");
+}
+
+#[test]
+fn golden_termux() {
+    assert_golden("termux");
+}
+
+#[test]
+fn golden_wsl() {
+    assert_golden("wsl");
+}